@@ -0,0 +1,177 @@
+use std::collections::HashSet;
+
+use proc_macro2::Ident;
+use quote::quote;
+use syn_helpers::syn::parse::{Parse, ParseStream};
+use syn_helpers::syn::punctuated::Punctuated;
+use syn_helpers::syn::{parse_quote, Expr, LitInt, Token};
+
+/// Parameters of a benchmark matrix, given as `key = value;` in any order
+///
+/// Only the name is required, the other parameters have defaults:
+///
+/// - `config`: the data set, any `utils::generator::Dataset` such as a `Seeder` over
+///   a `Generator`, `SeederConfig::new(1400, 30, 2, 4, 4, 1000, false)`
+/// - `seed`: `42`
+/// - `parser`: the nom parser, `parse_data`
+/// - `buffers`: work buffers, `BufferPreallocated::new(1_048_576)`, or `buffer` for a single one
+/// - `heuristics`: `StartGroupByParser` over `start_group_parenthesis`
+/// - `chunk_sizes`: sizes of the chunks of data fed to the driver, `4096`
+///
+/// Lists are separated by commas, a benchmark is generated for
+/// each combination of chunk size, buffer and heuristic. Combinations
+/// must have distinct names, see [bench_id].
+pub struct BenchesConfiguration {
+    pub name: Ident,
+    pub config: Expr,
+    pub seed: u64,
+    pub parser: Expr,
+    pub buffers: Vec<Expr>,
+    pub heuristics: Vec<Expr>,
+    pub chunk_sizes: Vec<u64>,
+}
+
+impl Parse for BenchesConfiguration {
+    fn parse(input: ParseStream) -> syn_helpers::syn::Result<Self> {
+        let mut name = None;
+        let mut config = None;
+        let mut seed = None;
+        let mut parser = None;
+        let mut buffers = None;
+        let mut heuristics = None;
+        let mut chunk_sizes = None;
+
+        while !input.is_empty() {
+            let key: Ident = input.parse()?;
+            input.parse::<Token![=]>()?;
+            let duplicated = match key.to_string().as_str() {
+                "name" => name.replace(input.parse()?).is_some(),
+                "config" => config.replace(input.parse()?).is_some(),
+                "seed" => seed
+                    .replace(input.parse::<LitInt>()?.base10_parse()?)
+                    .is_some(),
+                "parser" => parser.replace(input.parse()?).is_some(),
+                "buffers" => buffers.replace(parse_list(input)?).is_some(),
+                "buffer" => buffers.replace(vec![input.parse()?]).is_some(),
+                "heuristics" => heuristics.replace(parse_list(input)?).is_some(),
+                "chunk_sizes" => {
+                    let sizes = parse_list::<LitInt>(input)?
+                        .iter()
+                        .map(LitInt::base10_parse)
+                        .collect::<syn_helpers::syn::Result<_>>()?;
+                    chunk_sizes.replace(sizes).is_some()
+                }
+                _ => {
+                    return Err(syn_helpers::syn::Error::new(
+                        key.span(),
+                        format!(
+                            "Unknown parameter {key}, expected one of name, config, seed, \
+                             parser, buffer, buffers, heuristics or chunk_sizes"
+                        ),
+                    ))
+                }
+            };
+            if duplicated {
+                return Err(syn_helpers::syn::Error::new(
+                    key.span(),
+                    format!("Parameter {key} is given twice"),
+                ));
+            }
+            if !input.is_empty() {
+                input.parse::<Token![;]>()?;
+            }
+        }
+
+        let configuration = BenchesConfiguration {
+            name: name.ok_or_else(|| input.error("Expected name = "))?,
+            config: config.unwrap_or_else(|| {
+                parse_quote!(::utils::seeder::SeederConfig::new(
+                    1400, 30, 2, 4, 4, 1000, false
+                ))
+            }),
+            seed: seed.unwrap_or(42),
+            parser: parser.unwrap_or_else(|| parse_quote!(::utils::parsers::parse_data)),
+            buffers: buffers.unwrap_or_else(|| {
+                vec![parse_quote!(
+                    ::nom_stream_parser::buffers::preallocated::BufferPreallocated::new(1_048_576)
+                )]
+            }),
+            heuristics: heuristics.unwrap_or_else(|| {
+                vec![parse_quote!(::nom_stream_parser::StartGroupByParser {
+                    parser: ::utils::parsers::start_group_parenthesis,
+                    start_character: b"(",
+                })]
+            }),
+            chunk_sizes: chunk_sizes.unwrap_or_else(|| vec![4096]),
+        };
+        configuration.check_bench_ids()?;
+        Ok(configuration)
+    }
+}
+
+fn parse_list<T: Parse>(input: ParseStream) -> syn_helpers::syn::Result<Vec<T>> {
+    let list = Punctuated::<T, Token![,]>::parse_separated_nonempty(input)?;
+    Ok(list.into_iter().collect())
+}
+
+/// Name of the benchmark of a combination
+pub fn bench_id(driver: &str, size: u64, buffer: &Expr, heuristic: &Expr) -> String {
+    format!(
+        "{driver}/chunk_{size}/{}/{}",
+        label(buffer),
+        label(heuristic)
+    )
+}
+
+/// Short name of a buffer or a heuristic in the benchmark id, the type and the
+/// arguments of `BufferPreallocated::new(1024)` or the type of `StartGroupByParser { .. }`
+pub fn label(expr: &Expr) -> String {
+    match expr {
+        Expr::Call(call) => match &*call.func {
+            Expr::Path(path) if path.path.segments.len() > 1 => {
+                let segments = &path.path.segments;
+                let args = &call.args;
+                format!("{}({})", segments[segments.len() - 2].ident, quote!(#args))
+            }
+            func => label(func),
+        },
+        Expr::MethodCall(call) => label(&call.receiver),
+        Expr::Path(path) => path.path.segments.last().unwrap().ident.to_string(),
+        Expr::Struct(expr) => expr.path.segments.last().unwrap().ident.to_string(),
+        Expr::Paren(expr) => label(&expr.expr),
+        expr => quote!(#expr).to_string(),
+    }
+}
+
+impl BenchesConfiguration {
+    /// Every combination of chunk size, buffer and heuristic
+    pub fn matrix(&self) -> impl Iterator<Item = (u64, &Expr, &Expr)> {
+        self.chunk_sizes.iter().flat_map(move |&size| {
+            self.buffers.iter().flat_map(move |buffer| {
+                self.heuristics
+                    .iter()
+                    .map(move |heuristic| (size, buffer, heuristic))
+            })
+        })
+    }
+
+    /// Reject the combinations with the same benchmark id, criterion panics on them
+    fn check_bench_ids(&self) -> syn_helpers::syn::Result<()> {
+        let mut ids = HashSet::new();
+        for (size, buffer, heuristic) in self.matrix() {
+            let id = bench_id("", size, buffer, heuristic);
+            if !ids.insert(id) {
+                return Err(syn_helpers::syn::Error::new_spanned(
+                    quote!(#buffer #heuristic),
+                    format!(
+                        "Benchmark chunk_{size}/{}/{} is given twice, buffers \
+                         and heuristics must have distinct types or arguments",
+                        label(buffer),
+                        label(heuristic)
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
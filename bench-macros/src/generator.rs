@@ -0,0 +1,101 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn_helpers::syn::parse_macro_input;
+
+use crate::configuration::{bench_id, BenchesConfiguration};
+
+/// Stream parser driving the benchmarks
+#[derive(Clone, Copy)]
+pub enum Driver {
+    Iterator,
+    Reader,
+}
+
+impl Driver {
+    /// Name of the driver in the benchmark id
+    fn name(self) -> &'static str {
+        match self {
+            Driver::Iterator => "iterator",
+            Driver::Reader => "reader",
+        }
+    }
+
+    fn stream_parser(self) -> TokenStream {
+        match self {
+            Driver::Iterator => {
+                quote!(::nom_stream_parser::stream_parsers::sync_iterator::StreamParser)
+            }
+            Driver::Reader => {
+                quote!(::nom_stream_parser::stream_parsers::sync_reader::StreamParser)
+            }
+        }
+    }
+}
+
+pub fn impl_generate_bench(
+    input: proc_macro::TokenStream,
+    driver: Driver,
+) -> proc_macro::TokenStream {
+    let bench_configs = parse_macro_input!(input as BenchesConfiguration);
+    let bench_name = &bench_configs.name;
+    let bench_name_string = bench_name.to_string();
+    let config = &bench_configs.config;
+    let seed = bench_configs.seed;
+    let parser = &bench_configs.parser;
+    let stream_parser = driver.stream_parser();
+
+    let benches = bench_configs.matrix().map(|(size, buffer, heuristic)| {
+        let id = bench_id(driver.name(), size, buffer, heuristic);
+        let size = size as usize;
+        quote! {
+            {
+                let mut work_buffer = #buffer;
+                group.bench_function(#id, |b| {
+                    b.iter(|| {
+                        // Each iteration parses the data from an empty work buffer
+                        ::nom_stream_parser::Buffer::reset(&mut work_buffer);
+                        let source = ::utils::source::Source::new(data).with_chunk_size(#size);
+                        #stream_parser::new(
+                            ::criterion::black_box(source),
+                            &mut work_buffer,
+                            #parser,
+                            #heuristic,
+                        )
+                        .filter_map(Result::ok)
+                        .for_each(|item| {
+                            ::criterion::black_box(item);
+                        })
+                    })
+                });
+            }
+        }
+    });
+
+    let fn_name = format_ident!("bench_{}", bench_name_string);
+    let tokens = quote! {
+        fn #fn_name(c: &mut ::criterion::Criterion) {
+            let config = #config;
+            ::utils::seeder::raw_data(&config, #seed, |data| {
+                let mut group =
+                    c.benchmark_group(format!("{} seed={}", #bench_name_string, #seed));
+                group.throughput(::criterion::Throughput::Bytes(data.len() as u64));
+                #(#benches)*
+                group.finish();
+            });
+        }
+    };
+
+    let criterion_group = quote! {
+        ::criterion::criterion_group!(
+            #bench_name,
+            #fn_name
+        );
+    };
+
+    let tokens = quote! {
+        #tokens
+        #criterion_group
+    };
+
+    tokens.into()
+}
@@ -1,5 +1,7 @@
 mod bench_iterator;
 mod bench_reader;
+mod configuration;
+mod generator;
 
 #[proc_macro]
 pub fn generate_bench_iterator(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
@@ -1,52 +1,40 @@
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use criterion::criterion_main;
 
 use bench_macros::generate_bench_reader;
 use nom_stream_parser::buffers::preallocated::BufferPreallocated;
-use nom_stream_parser::{Buffer, StartGroupByParser, StreamParserError};
+use nom_stream_parser::heuristic::Increment;
+use nom_stream_parser::StartGroupByParser;
+use utils::generator::{Parenthesis, Seeder};
 use utils::parsers::{parse_data, start_group_parenthesis};
-use utils::seeder::{raw_data, SeederConfig};
-
-pub fn parse<B: Buffer>(
-    source: &[u8],
-    work_buffer: &mut B,
-) -> Result<Vec<Vec<u8>>, StreamParserError> {
-    let parser = parse_data;
-    let search_group_heuristic = StartGroupByParser {
-        parser: start_group_parenthesis,
-        start_character: b"(",
-    };
-    let stream = nom_stream_parser::stream_parsers::sync_reader::StreamParser::new(
-        source,
-        work_buffer,
-        parser,
-        search_group_heuristic,
-    );
-    let result: Vec<Vec<u8>> = stream.filter_map(|x| x.ok()).collect();
-    Ok(result)
-}
+use utils::seeder::SeederConfig;
 
 generate_bench_reader!(
     name = big_data;
     config = SeederConfig::new(1400, 30, 2, 4, 4, 1000, false);
-    seed = 42;
-    parser = parse;
-    buffer = BufferPreallocated::new(1_048_576);
+    parser = parse_data;
+    buffers = BufferPreallocated::new(65_536), BufferPreallocated::new(1_048_576);
+    heuristics = Increment, StartGroupByParser { parser: start_group_parenthesis, start_character: b"(" };
+    chunk_sizes = 64, 4096
 );
 
 generate_bench_reader!(
     name = hell_data;
     config = SeederConfig::new(14000, 30, 2, 4, 4, 10000, false);
-    seed = 42;
-    parser = parse;
-    buffer = BufferPreallocated::new(500_048_576);
+    buffers = BufferPreallocated::new(500_048_576);
+    heuristics = StartGroupByParser { parser: start_group_parenthesis, start_character: b"(" };
 );
 
 generate_bench_reader!(
     name = small_data;
     config = SeederConfig::new(14, 30, 2, 4, 4, 10, false);
-    seed = 42;
-    parser = parse;
-    buffer = BufferPreallocated::new(1_048_576);
+    heuristics = Increment, StartGroupByParser { parser: start_group_parenthesis, start_character: b"(" };
+);
+
+generate_bench_reader!(
+    name = generated_data;
+    config = Seeder::new(Parenthesis::default(), 1400).with_corrupted_probability(5);
+    heuristics = StartGroupByParser { parser: start_group_parenthesis, start_character: b"(" };
+    chunk_sizes = 64, 4096
 );
 
-criterion_main!(small_data, big_data, hell_data);
+criterion_main!(small_data, big_data, hell_data, generated_data);
@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use playground_fuzz::equivalence::Driver;
+use playground_fuzz::{differential, Input};
+
+fuzz_target!(|input: Input| {
+    differential(&input, Driver::Reader);
+});
@@ -1,37 +1,19 @@
 #![no_main]
 
 use libfuzzer_sys::fuzz_target;
-use rand::SeedableRng;
-use rand_chacha::ChaCha8Rng;
 
-use nom_stream_parser::buffers::preallocated::BufferPreallocated;
-use nom_stream_parser::builder::StreamParserBuilder;
-use nom_stream_parser::StartGroupByParser;
-use utils::parsers::{parse_data, start_group_parenthesis};
+use playground_fuzz::equivalence::Driver;
+use playground_fuzz::{seeded, start_group_heuristic};
+use utils::parsers::parse_data;
 use utils::seeder::SeederConfig;
 
 fuzz_target!(|seed: u64| {
-    let mut rng = ChaCha8Rng::seed_from_u64(seed);
-
-    let config = SeederConfig::new(1400, 30, 2, 4, 4, 1000, false);
-    let (data_to_parse, expected) = config.generate(&mut rng);
-
-    let mut work_buffer = BufferPreallocated::new(1_048_576).with_name("work buffer");
-
-    let heuristic = StartGroupByParser {
-        parser: start_group_parenthesis,
-        start_character: b"(",
-    };
-
-    let stream = StreamParserBuilder::default()
-        .work_buffer(&mut work_buffer)
-        .parser(parse_data)
-        .reader(data_to_parse.as_slice())
-        .heuristic(heuristic)
-        .build()
-        .unwrap()
-        .stream();
-
-    let result: Vec<Vec<u8>> = stream.filter_map(|x| x.ok()).collect();
-    assert_eq!(expected, result);
+    let generator = SeederConfig::new(1400, 30, 2, 4, 4, 1000, false);
+    seeded(
+        generator,
+        seed,
+        Driver::Reader,
+        parse_data,
+        start_group_heuristic(),
+    );
 });
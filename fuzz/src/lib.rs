@@ -1,24 +1,189 @@
-use nom_stream_parser::{
-    Buffer, DataSource, EnumHeuristic, StartGroupByParser, StreamParser, StreamParserError,
-};
+use std::fmt::Debug;
+
+use libfuzzer_sys::arbitrary;
+use libfuzzer_sys::arbitrary::{Arbitrary, Unstructured};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use nom_stream_parser::buffers::preallocated::BufferPreallocated;
+use nom_stream_parser::builder::StreamParserBuilder;
+use nom_stream_parser::heuristic::{Heuristic, Increment};
+use nom_stream_parser::stream_parsers::{sync_iterator, sync_reader};
+use nom_stream_parser::{ParserFunction, StartGroupByParser};
+use utils::generator::{Dataset, Generator, Seeder};
 use utils::parsers::{parse_data, start_group_parenthesis};
-use utils::source::Source;
-
-pub fn parse<B: Buffer>(
-    source: Source,
-    work_buffer: &mut B,
-) -> Result<Vec<Vec<u8>>, StreamParserError> {
-    let parser = parse_data;
-    let search_group_heuristic = StartGroupByParser {
+use utils::source::{Chunked, Source};
+
+use equivalence::{reference, start_group_by_parser, Driver};
+
+#[path = "../../tests/equivalence/mod.rs"]
+pub mod equivalence;
+
+/// Arbitrary bytes fed to the stream parser in arbitrary chunks
+#[derive(Debug)]
+pub struct Input {
+    pub data: Vec<u8>,
+    /// Sizes of the successive chunks, repeated until all the data are chunked
+    pub chunk_sizes: Vec<u8>,
+    pub buffer_size: u16,
+}
+
+impl<'a> Arbitrary<'a> for Input {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Input {
+            buffer_size: u.arbitrary()?,
+            chunk_sizes: u.arbitrary()?,
+            data: u.arbitrary()?,
+        })
+    }
+
+    /// The data are the raw rest of the fuzz input, after the buffer size and the chunk sizes
+    fn arbitrary_take_rest(mut u: Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Input {
+            buffer_size: u.arbitrary()?,
+            chunk_sizes: u.arbitrary()?,
+            data: u.take_rest().to_vec(),
+        })
+    }
+}
+
+impl Input {
+    /// Split the data following the chunk sizes, in a single chunk without any size
+    pub fn chunks(&self) -> Vec<&[u8]> {
+        let mut chunks = vec![];
+        let mut sizes = self.chunk_sizes.iter().cycle();
+        let mut remaining = self.data.as_slice();
+        while !remaining.is_empty() {
+            let size = sizes.next().map_or(remaining.len(), |&size| size as usize);
+            let (chunk, rest) = remaining.split_at(size.clamp(1, remaining.len()));
+            chunks.push(chunk);
+            remaining = rest;
+        }
+        chunks
+    }
+
+    /// Whether the work buffer can hold the whole data, so that no chunking
+    /// can overflow it and the items must match the reference parse
+    pub fn buffer_holds_data(&self) -> bool {
+        self.buffer_size as usize >= self.data.len()
+    }
+}
+
+pub fn start_group_heuristic() -> StartGroupByParser<'static> {
+    StartGroupByParser {
         parser: start_group_parenthesis,
         start_character: b"(",
+    }
+}
+
+/// Parse the frames of any generator, seeded by the fuzzer, with a work buffer
+/// holding the whole data, the items must be the values of the valid frames
+pub fn seeded<G, H>(
+    generator: G,
+    seed: u64,
+    driver: Driver,
+    parser: ParserFunction<G::Value>,
+    heuristic: H,
+) where
+    G: Generator,
+    G::Value: Debug + PartialEq,
+    H: Heuristic,
+{
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let (data, expected) = Seeder::new(generator, 1400).generate(&mut rng);
+
+    let mut work_buffer = BufferPreallocated::new(1_048_576).with_name("work buffer");
+    let builder = StreamParserBuilder::with_heuristic(heuristic)
+        .work_buffer(&mut work_buffer)
+        .parser(parser);
+
+    let result: Vec<G::Value> = match driver {
+        Driver::Iterator => {
+            let source = Source::new(&data).with_chunk_size(4096);
+            let stream = builder.iterator(source).build().unwrap().stream();
+            stream.filter_map(|x| x.ok()).collect()
+        }
+        Driver::Reader => {
+            let stream = builder.reader(data.as_slice()).build().unwrap().stream();
+            stream.filter_map(|x| x.ok()).collect()
+        }
+    };
+    assert_eq!(expected, result);
+}
+
+/// Parse the chunked data with the driver, panicking if the stream doesn't
+/// end, every item consuming at least a byte of the data or a chunk
+///
+/// A loop inside a single call to `next` never yields, it is reported
+/// by libFuzzer as a timeout.
+pub fn parse<H: Heuristic>(input: &Input, driver: Driver, heuristic: H) -> Vec<Option<Vec<u8>>> {
+    let chunks = input.chunks();
+    let max_items = input.data.len() + chunks.len() + 1;
+    let mut work_buffer = BufferPreallocated::new((input.buffer_size as usize).max(1));
+
+    let items: Vec<_> = match driver {
+        Driver::Iterator => sync_iterator::StreamParser::new(
+            chunks.into_iter(),
+            &mut work_buffer,
+            parse_data,
+            heuristic,
+        )
+        .map(Result::ok)
+        .take(max_items + 1)
+        .collect(),
+        Driver::Reader => sync_reader::StreamParser::new(
+            Chunked::new(chunks),
+            &mut work_buffer,
+            parse_data,
+            heuristic,
+        )
+        .map(Result::ok)
+        .take(max_items + 1)
+        .collect(),
     };
-    let stream = StreamParser::new(
-        DataSource::<_, &[u8]>::Iterator(source),
-        work_buffer,
-        parser,
-        EnumHeuristic::SearchGroup(search_group_heuristic),
+    assert!(
+        items.len() <= max_items,
+        "{driver:?} stream parser doesn't end, more than {max_items} items"
     );
-    let result: Vec<Vec<u8>> = stream.filter_map(|x| x.ok()).collect();
-    Ok(result)
+    items
+}
+
+/// Compare the parse of the chunked data with the reference parse of the
+/// whole data, for both heuristics, also with a work buffer of the size of the data
+pub fn differential(input: &Input, driver: Driver) {
+    check(input, driver);
+
+    // A work buffer just holding the data fills up at the end of the data
+    if let Ok(buffer_size) = u16::try_from(input.data.len()) {
+        let input = Input {
+            data: input.data.clone(),
+            chunk_sizes: input.chunk_sizes.clone(),
+            buffer_size,
+        };
+        check(&input, driver);
+    }
+}
+
+fn check(input: &Input, driver: Driver) {
+    let found = parse(input, driver, Increment);
+    if input.buffer_holds_data() {
+        assert_eq!(
+            reference(parse_data, |_| Some(0), &input.data),
+            found,
+            "Increment heuristic"
+        );
+    }
+
+    let found = parse(input, driver, start_group_heuristic());
+    if input.buffer_holds_data() {
+        assert_eq!(
+            reference(
+                parse_data,
+                start_group_by_parser(&start_group_heuristic()),
+                &input.data
+            ),
+            found,
+            "StartGroupByParser heuristic"
+        );
+    }
 }
@@ -1 +1,2 @@
 pub mod preallocated;
+pub mod slice;
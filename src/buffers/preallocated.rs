@@ -1,7 +1,7 @@
 use std::ops::{Deref, DerefMut};
 
-use crate::debug;
 use crate::errors::StreamParserError;
+use crate::trace::event;
 use crate::traits::Buffer;
 
 /// A buffer of preallocated heap data
@@ -29,7 +29,7 @@ impl<'a> BufferPreallocated<'a> {
         }
     }
 
-    /// Define a name to buffer, mostly for debugging purpose
+    /// Define a name to buffer, used by traces and as label of the parser metrics
     pub fn with_name(mut self, name: &'a str) -> Self {
         self.name = name;
         self
@@ -48,23 +48,27 @@ impl Buffer for BufferPreallocated<'_> {
         other: &[u8],
         evinceable: Option<usize>,
     ) -> Result<bool, StreamParserError> {
-        tracing::trace!("[{}] Appending to buffer", self.name);
-
         let mut eviction = false;
         let free_space = self.buffer.len() - self.cursor;
-        tracing::trace!("[{}] free space : {free_space}", self.name);
-        tracing::trace!("[{}] other len : {}", self.name, other.len());
+        event!(
+            trace,
+            buffer = self.name,
+            free_space,
+            size = other.len(),
+            "Appending to buffer"
+        );
         // Attempt to append data size greater than the available space
         if other.len() > free_space {
             // Trying to evince useless data
-            tracing::debug!(
-                "[{}] Evinceable ? {} {:?}",
-                self.name,
-                evinceable.is_some(),
-                evinceable
-            );
-
-            tracing::trace!("Before eviction {}", debug!(&self.buffer[..self.cursor]));
+            event!(debug, buffer = self.name, evinceable = ?evinceable, "Buffer full");
+
+            // Even without useless data, the buffer is too small, it is left untouched
+            if other.len() > free_space + evinceable.unwrap_or(0) {
+                return Err(StreamParserError::ExceededBuffer {
+                    buffer_size: self.buffer.len(),
+                    data_size: other.len(),
+                });
+            }
 
             self.evince(evinceable, other)?;
             eviction = true;
@@ -72,12 +76,11 @@ impl Buffer for BufferPreallocated<'_> {
 
         self.buffer[self.cursor..other.len() + self.cursor].clone_from_slice(other);
         self.cursor += other.len();
-        tracing::trace!("After eviction {}", debug!(&self.buffer[..self.cursor]));
         Ok(eviction)
     }
 
     fn copy_from(&mut self, source: &Self, evinceable: Option<usize>) {
-        tracing::trace!("[{}] Cloning from buffer", self.name);
+        event!(trace, buffer = self.name, "Cloning from buffer");
 
         // Re-init existing data
         self.clear();
@@ -85,7 +88,7 @@ impl Buffer for BufferPreallocated<'_> {
     }
 
     fn clear(&mut self) {
-        tracing::trace!("[{}] Clearing buffer", self.name);
+        event!(trace, buffer = self.name, "Clearing buffer");
         self.cursor = 0;
     }
 
@@ -108,8 +111,12 @@ impl Buffer for BufferPreallocated<'_> {
                 data_size: other.len(),
             }),
             Some(evince_number) => {
-                tracing::debug!("[{}] Evincing data", self.name);
-                tracing::trace!("[{}] Evincing {} bytes", self.name, evince_number);
+                event!(
+                    debug,
+                    buffer = self.name,
+                    size = evince_number,
+                    "Evincing data"
+                );
                 for (i, x) in (evince_number..self.cursor).enumerate() {
                     self.buffer[i] = self.buffer[x];
                 }
@@ -118,6 +125,10 @@ impl Buffer for BufferPreallocated<'_> {
             }
         }
     }
+
+    fn name(&self) -> &str {
+        self.name
+    }
 }
 
 #[cfg(test)]
@@ -136,4 +147,12 @@ mod tests {
         buffer.append(b"123", Some(2)).unwrap();
         assert_eq!(&b"cde123", &buffer.deref());
     }
+
+    #[test]
+    fn append_exceeding_buffer() {
+        let mut buffer = BufferPreallocated::new(6);
+        buffer.append(b"abcde", None).unwrap();
+        assert!(buffer.append(b"1234", Some(2)).is_err());
+        assert_eq!(b"abcde", buffer.deref());
+    }
 }
@@ -0,0 +1,117 @@
+use std::ops::Deref;
+
+use crate::errors::StreamParserError;
+use crate::traits::Buffer;
+
+/// A read-only buffer over data already in memory, such as a memory mapped file
+///
+/// The buffer is created full of the data, evincing data only moves the start
+/// of the buffer, so the data are never copied. No data can be appended,
+/// dropping the buffered data leaves the buffer empty.
+pub struct BufferSlice<S> {
+    data: S,
+    /// Start of the buffered data
+    start: usize,
+    /// End of the buffered data
+    end: usize,
+}
+
+impl<S: Deref<Target = [u8]>> BufferSlice<S> {
+    pub fn new(data: S) -> Self {
+        let end = data.len();
+        BufferSlice {
+            data,
+            start: 0,
+            end,
+        }
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl BufferSlice<memmap2::Mmap> {
+    /// Map a file read-only, the file must not be modified while it is mapped
+    pub fn map(file: &std::fs::File) -> std::io::Result<Self> {
+        // SAFETY: the mapping is read-only, the file must not
+        // be modified by other processes while mapped
+        let mmap = unsafe { memmap2::Mmap::map(file)? };
+        Ok(BufferSlice::new(mmap))
+    }
+}
+
+impl<S: Deref<Target = [u8]>> Deref for BufferSlice<S> {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        &self.data[self.start..self.end]
+    }
+}
+
+impl<S: Deref<Target = [u8]>> Buffer for BufferSlice<S> {
+    fn append(
+        &mut self,
+        other: &[u8],
+        _evinceable: Option<usize>,
+    ) -> Result<bool, StreamParserError> {
+        if !other.is_empty() {
+            return Err(StreamParserError::ExceededBuffer {
+                buffer_size: self.data.len(),
+                data_size: other.len(),
+            });
+        }
+        Ok(false)
+    }
+
+    fn copy_from(&mut self, source: &Self, _evinceable: Option<usize>) {
+        // The data are read-only, the buffers are expected to be over the same data
+        self.start = source.start.min(self.data.len());
+        self.end = source.end.clamp(self.start, self.data.len());
+    }
+
+    fn clear(&mut self) {
+        self.start = self.end;
+    }
+
+    fn incr_cursor(&mut self, offset: usize) {
+        self.end += offset;
+    }
+
+    fn get_write_buffer(&mut self) -> &mut [u8] {
+        &mut []
+    }
+
+    fn reset(&mut self) {
+        self.start = self.end;
+    }
+
+    fn evince(&mut self, evinceable: Option<usize>, other: &[u8]) -> Result<(), StreamParserError> {
+        match evinceable {
+            Some(0) | None => Err(StreamParserError::ExceededBuffer {
+                buffer_size: self.data.len(),
+                data_size: other.len(),
+            }),
+            Some(evince_number) => {
+                self.start += evince_number;
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Deref;
+
+    use crate::buffers::slice::BufferSlice;
+    use crate::traits::Buffer;
+
+    #[test]
+    fn evince_without_copy() {
+        let data = *b"abcdef";
+        let mut buffer = BufferSlice::new(&data[..]);
+        buffer.evince(Some(2), b"").unwrap();
+        assert_eq!(&b"cdef", &buffer.deref());
+        assert!(buffer.append(b"g", None).is_err());
+        buffer.clear();
+        assert!(buffer.is_empty());
+    }
+}
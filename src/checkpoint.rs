@@ -0,0 +1,16 @@
+use crate::parser_state::{ParsableState, SearchState};
+
+/// Snapshot of a stream parser allowing to resume parsing
+/// after a restart without yielding twice the same data
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Checkpoint {
+    /// Offset of the first byte not consumed yet, relative to the position of the
+    /// source when the stream parser was created, every data before this offset
+    /// has already been yielded or discarded
+    pub offset: u64,
+    /// Parsing state at the time of the snapshot
+    pub state: (SearchState, ParsableState),
+    /// Internal state of the heuristic, see [crate::heuristic::Heuristic::state]
+    pub heuristic: Vec<u8>,
+}
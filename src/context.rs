@@ -0,0 +1,30 @@
+use crate::traits::Parse;
+
+/// A parser needing a context, shared between the parsing of
+/// each data, e.g. a schema announced in a header or a sequence number
+///
+/// The parser is called again on the same data when it returns
+/// [nom::Err::Incomplete], so the context should only be altered on success.
+pub struct ContextParser<C, F> {
+    /// The context given to each call of the parser
+    pub context: C,
+    parser: F,
+}
+
+impl<C, F> ContextParser<C, F> {
+    pub fn new<R>(context: C, parser: F) -> Self
+    where
+        F: for<'i> FnMut(&mut C, &'i [u8]) -> nom::IResult<&'i [u8], R>,
+    {
+        ContextParser { context, parser }
+    }
+}
+
+impl<C, F, R> Parse<R> for ContextParser<C, F>
+where
+    F: for<'i> FnMut(&mut C, &'i [u8]) -> nom::IResult<&'i [u8], R>,
+{
+    fn parse<'i>(&mut self, input: &'i [u8]) -> nom::IResult<&'i [u8], R> {
+        (self.parser)(&mut self.context, input)
+    }
+}
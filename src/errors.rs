@@ -1,8 +1,9 @@
 use nom::error::Error;
 use thiserror::Error;
 
-use crate::debug;
+use crate::trace::Preview;
 
+#[allow(dead_code)]
 #[derive(Debug, PartialEq)]
 pub struct ExceedBuffer;
 
@@ -21,11 +22,45 @@ pub enum StreamParserError {
     },
     #[error("Buffer overflow : buffer size {buffer_size}")]
     ExceededBufferUnknownSize { buffer_size: usize },
+    #[error("No data available yet")]
+    Pending,
+    #[error("Timeout : partial frame of {} bytes dropped", .dropped.len())]
+    Timeout { dropped: Vec<u8> },
+    #[error("No data received for {idle:?}")]
+    Idle { idle: std::time::Duration },
+    #[error(
+        "Frame too large : {dropped} bytes dropped, at most {max_frame_size} bytes are allowed"
+    )]
+    FrameTooLarge {
+        max_frame_size: usize,
+        dropped: usize,
+    },
+    #[error("Too much garbage : no group start found in more than {max_garbage} bytes")]
+    GarbageExceeded { max_garbage: usize },
+    #[error(
+        "Too many errors : {max_consecutive_errors} consecutive errors, dropping buffered data"
+    )]
+    TooManyErrors { max_consecutive_errors: usize },
+    #[error("Too many channels : at most {max_channels} channels can be demultiplexed")]
+    TooManyChannels { max_channels: usize },
+    #[error("Duplicate fragment {index}")]
+    DuplicateFragment { index: usize },
+    #[error("Malformed fragment : fragment {index} conflicts with the last fragment {last}")]
+    MalformedFragment { index: usize, last: usize },
+    #[error("Incomplete message expired after receiving {fragments} fragments")]
+    ExpiredMessage { fragments: usize },
+    #[error("Layer {layer} error at offset {offset} : {source}")]
+    Layer {
+        layer: usize,
+        offset: u64,
+        source: Box<StreamParserError>,
+    },
 }
 
 impl From<nom::Err<Error<&[u8]>>> for StreamParserError {
     fn from(value: nom::Err<Error<&[u8]>>) -> Self {
-        let mapped_error = value.map_input(|error_slice| debug!(error_slice).to_string());
+        // Only a bounded preview of the remaining input is kept, not a copy of it
+        let mapped_error = value.map_input(|error_slice| Preview(error_slice).to_string());
         StreamParserError::Nom(mapped_error)
     }
 }
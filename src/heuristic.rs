@@ -1,9 +1,11 @@
 use nom::Parser;
 
-use crate::debug;
 use crate::errors::StreamParserError;
 use crate::logic::ReturnState;
 use crate::parser_state::{ParsableState, SearchState};
+use crate::trace::event;
+#[cfg(feature = "trace")]
+use crate::trace::Preview;
 use crate::traits::{Buffer, ParserFunctionStartGroup};
 
 pub trait Heuristic {
@@ -13,8 +15,20 @@ pub trait Heuristic {
         state: &mut (SearchState, ParsableState),
         cursor: &mut usize,
     ) -> Result<Option<ReturnState<R>>, StreamParserError>;
+
+    /// Serialize the internal state of the heuristic, stored in [crate::Checkpoint]
+    fn state(&self) -> Vec<u8> {
+        vec![]
+    }
+
+    /// Restore an internal state previously serialized by [Heuristic::state]
+    fn restore(&mut self, _state: &[u8]) {}
+
+    /// Forget the internal state, as if the heuristic was just created
+    fn reset(&mut self) {}
 }
 
+#[derive(Clone)]
 pub struct Increment;
 
 impl Heuristic for Increment {
@@ -29,6 +43,7 @@ impl Heuristic for Increment {
 }
 
 /// Data structure used by [EnumHeuristic::SearchGroup]
+#[derive(Clone)]
 pub struct StartGroupByParser<'a> {
     /// The parser which define whether the
     /// cursor reaches a start group
@@ -45,57 +60,41 @@ impl<'a> Heuristic for StartGroupByParser<'a> {
         cursor: &mut usize,
     ) -> Result<Option<ReturnState<R>>, StreamParserError> {
         let input = &work_buffer[*cursor..];
-        tracing::debug!("Search for a new group start");
-        tracing::trace!("In {}", debug!(input));
+        event!(trace, input = %Preview(input), "Search for a new group start");
         // On vérifie si le début de groupe est dans le buffer mémoire
         let result_search_for_start =
             nom::bytes::complete::take_until::<_, _, ()>(self.start_character)(input);
 
         match result_search_for_start {
             Err(_) => {
-                tracing::debug!("No group start found");
-                tracing::trace!("In {}", debug!(input));
-                tracing::debug!("Cleaning buffers");
+                event!(debug, decision = "no_start", discarded = input.len());
                 //save_buffer.clear();
                 work_buffer.clear();
                 *cursor = 0;
-                tracing::trace!("Asking for more data");
                 state.0 = SearchState::SearchForStart;
                 return Ok(Some(ReturnState::NeedMoreData));
             }
             Ok((remain, garbage)) => {
-                tracing::debug!("Found group start");
-                tracing::trace!(
-                    "In {} remain = {} garbage = {}",
-                    debug!(input),
-                    debug!(remain),
-                    debug!(garbage)
-                );
-
                 let result_group_start_complete = self.parser.parse(remain);
 
                 match result_group_start_complete {
                     Ok((_remain, garbage2)) => {
-                        tracing::debug!("Found complete group start");
-                        tracing::trace!(
-                            "In {} remain = {} garbage2 = {}",
-                            debug!(input),
-                            debug!(remain),
-                            debug!(garbage2)
+                        event!(
+                            debug,
+                            decision = "start_found",
+                            skipped = garbage.len() + garbage2.len()
                         );
                         *cursor += garbage.len() + garbage2.len();
                         state.0 = SearchState::StartFound
                     }
                     Err(nom::Err::Incomplete(_)) => {
+                        event!(debug, decision = "incomplete");
                         return Ok(Some(ReturnState::NeedMoreData));
                     }
                     Err(_) => {
-                        tracing::debug!("No group start found");
-                        tracing::trace!("In {}", debug!(input));
-                        tracing::debug!("Cleaning buffers");
+                        event!(debug, decision = "no_start", discarded = input.len());
                         work_buffer.clear();
                         *cursor = 0;
-                        tracing::trace!("Asking for more data");
                         state.0 = SearchState::SearchForStart;
                         return Ok(Some(ReturnState::NeedMoreData));
                     }
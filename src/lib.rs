@@ -1,15 +1,27 @@
+pub use checkpoint::Checkpoint;
+pub use context::ContextParser;
 pub use errors::StreamParserError;
 pub use heuristic::StartGroupByParser;
+pub use limits::Limits;
+pub use parser_state::{ParsableState, SearchState};
 
-pub use crate::traits::{Buffer, ParserFunction, ParserFunctionStartGroup};
+pub use crate::traits::{Buffer, Parse, ParserFunction, ParserFunctionStartGroup};
 
 pub mod buffers;
 #[cfg(feature = "builder")]
 pub mod builder;
+mod checkpoint;
+mod context;
 mod errors;
 pub mod heuristic;
+mod limits;
 mod logic;
+pub mod metrics;
 mod parser_state;
+pub mod sources;
+pub mod stats;
 pub mod stream_parsers;
+pub mod timeout;
+mod trace;
 mod traits;
 mod utils;
@@ -0,0 +1,30 @@
+/// Limits protecting the stream parsers against untrusted input
+///
+/// When a limit is hit, the offending data are dropped, a dedicated
+/// error is yielded and the parsing resumes with the next group start.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Limits {
+    /// Maximum amount of data buffered for a frame not parsed yet
+    pub max_frame_size: Option<usize>,
+    /// Maximum amount of data discarded by the heuristic without finding a group start
+    pub max_garbage: Option<usize>,
+    /// Maximum number of errors yielded in a row
+    pub max_consecutive_errors: Option<usize>,
+}
+
+impl Limits {
+    pub fn with_max_frame_size(mut self, max_frame_size: usize) -> Self {
+        self.max_frame_size = Some(max_frame_size);
+        self
+    }
+
+    pub fn with_max_garbage(mut self, max_garbage: usize) -> Self {
+        self.max_garbage = Some(max_garbage);
+        self
+    }
+
+    pub fn with_max_consecutive_errors(mut self, max_consecutive_errors: usize) -> Self {
+        self.max_consecutive_errors = Some(max_consecutive_errors);
+        self
+    }
+}
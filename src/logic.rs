@@ -1,11 +1,12 @@
-use std::cell::RefCell;
 use std::fmt::Debug;
 
-use crate::debug;
 use crate::errors::StreamParserError;
 use crate::heuristic::Heuristic;
 use crate::parser_state::{ParsableState, SearchState};
-use crate::traits::{Buffer, ParserFunction};
+#[cfg(feature = "trace")]
+use crate::trace::Preview;
+use crate::trace::{event, span};
+use crate::traits::{Buffer, Parse};
 
 pub(crate) type Logic<St, R> = Box<dyn FnMut(&mut St) -> Option<Result<R, StreamParserError>>>;
 
@@ -21,14 +22,14 @@ pub enum ReturnState<R> {
     Error(StreamParserError),
 }
 
-pub fn parse_internal<B: Buffer, R: Debug, H: Heuristic>(
+pub fn parse_internal<B: Buffer, R: Debug, H: Heuristic, P: Parse<R>>(
     work_buffer: &mut B,
     state: &mut (SearchState, ParsableState),
     cursor: &mut usize,
-    parser: ParserFunction<R>,
+    parser: &mut P,
     heuristic: &mut H,
 ) -> Result<Option<R>, StreamParserError> {
-    tracing::debug!("Parsing work buffer");
+    let _span = span!(TRACE, "frame", cursor = *cursor, state = ?state);
 
     let return_state = parsing_logic(work_buffer, state, cursor, parser, heuristic);
 
@@ -42,18 +43,18 @@ pub fn parse_internal<B: Buffer, R: Debug, H: Heuristic>(
             Ok(Some(data))
         }
         Ok(ReturnState::Error(err)) => {
-            tracing::debug!("Yield an error");
+            event!(debug, error = %err, "Yield an error");
             Err(err)
         }
         Err(err) => Err(err),
     }
 }
 
-fn parsing_logic<B: Buffer, R: Debug, H: Heuristic>(
+fn parsing_logic<B: Buffer, R: Debug, H: Heuristic, P: Parse<R>>(
     work_buffer: &mut B,
     state: &mut (SearchState, ParsableState),
     cursor: &mut usize,
-    parser: ParserFunction<R>,
+    parser: &mut P,
     heuristic: &mut H,
 ) -> Result<ReturnState<R>, StreamParserError>
 where
@@ -66,42 +67,25 @@ where
 
     let input = &work_buffer[*cursor..];
 
-    tracing::debug!("Parsing data {}", debug!(input));
+    event!(trace, input = %Preview(input), "Parsing data");
 
-    let result_parse = parser(input);
+    let result_parse = parser.parse(input);
 
     match result_parse {
         Ok((remain, data)) => {
-            tracing::debug!("Successfully parse tokens");
-            tracing::trace!("Found data = {data:?}");
-            tracing::trace!("Remaining token = {}", debug!(remain));
-            *cursor += input.len() - remain.len();
-            tracing::trace!(
-                "Shift cursor from {} to {}",
-                debug!(input),
-                debug!(&work_buffer[*cursor..])
-            );
+            let length = input.len() - remain.len();
+            event!(debug, length, "Parsed frame");
+            event!(trace, data = ?data);
+            *cursor += length;
             state.0 = SearchState::SearchForStart;
 
-            // if work_buffer[*cursor..].is_empty() {
-            //     save_buffer.clear()
-            // }
-
             return Ok(ReturnState::Data(data));
         }
         Err(nom::Err::Incomplete(_)) => {
-            tracing::debug!("Not enough data to decide");
-            tracing::trace!("In {}", debug!(input));
-            tracing::debug!("Asking for more data on incomplete data");
-            //save_buffer.clear();
-            //save_buffer.append(input, Some(*cursor))?;
+            event!(debug, buffered = input.len(), "Not enough data to decide");
         }
         Err(err) => {
-            tracing::trace!(
-                "Error in {} to {}",
-                debug!(input),
-                debug!(&work_buffer[*cursor..])
-            );
+            event!(debug, input = %Preview(input), "Parsing error");
 
             state.0 = SearchState::SearchForStart;
 
@@ -0,0 +1,255 @@
+//! Metrics of the stream parsers published through the `metrics` crate facade
+//!
+//! Without the `metrics` feature every recording is a no-op. Install a recorder,
+//! for instance a Prometheus exporter, to collect them. Each metric is labelled by
+//! the name of the parser, which defaults to the name of its work buffer.
+//!
+//! | Metric                                  | Kind      | Labels           |
+//! |-----------------------------------------|-----------|------------------|
+//! | `stream_parser_frames_total`            | counter   | `parser`         |
+//! | `stream_parser_frame_size_bytes`        | histogram | `parser`         |
+//! | `stream_parser_discarded_bytes_total`   | counter   | `parser`         |
+//! | `stream_parser_errors_total`            | counter   | `parser`, `kind` |
+//! | `stream_parser_heuristic_seconds`       | histogram | `parser`         |
+//! | `stream_parser_parser_seconds`          | histogram | `parser`         |
+
+#[cfg(feature = "metrics")]
+use std::cell::Cell;
+#[cfg(feature = "metrics")]
+use std::collections::HashMap;
+#[cfg(feature = "metrics")]
+use std::time::Instant;
+
+#[cfg(feature = "metrics")]
+use metrics::{Counter, Histogram};
+
+use crate::StreamParserError;
+
+pub const FRAMES: &str = "stream_parser_frames_total";
+pub const FRAME_SIZE: &str = "stream_parser_frame_size_bytes";
+pub const DISCARDED: &str = "stream_parser_discarded_bytes_total";
+pub const ERRORS: &str = "stream_parser_errors_total";
+pub const HEURISTIC_TIME: &str = "stream_parser_heuristic_seconds";
+pub const PARSER_TIME: &str = "stream_parser_parser_seconds";
+
+/// Stage of the parsing whose duration is measured
+#[derive(Clone, Copy)]
+pub(crate) enum Stage {
+    Heuristic,
+    Parser,
+}
+
+/// Handle recording the metrics of a parser instance
+pub(crate) struct Metrics {
+    #[cfg(feature = "metrics")]
+    handles: Handles,
+}
+
+/// Metrics of a parser instance, registered once by the recorder
+#[cfg(feature = "metrics")]
+struct Handles {
+    frames: Counter,
+    frame_size: Histogram,
+    discarded: Counter,
+    /// Errors counters by kind of error
+    errors: HashMap<&'static str, Counter>,
+    heuristic_time: Histogram,
+    parser_time: Histogram,
+    /// End of the last stage timed in the running parsing iteration,
+    /// the next stage starts there
+    last_stage_end: Cell<Option<Instant>>,
+}
+
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+impl Metrics {
+    pub fn new(name: &str) -> Self {
+        #[cfg(feature = "metrics")]
+        let name = metrics::SharedString::from(name.to_string());
+        Metrics {
+            #[cfg(feature = "metrics")]
+            handles: Handles {
+                frames: metrics::counter!(FRAMES, "parser" => name.clone()),
+                frame_size: metrics::histogram!(FRAME_SIZE, "parser" => name.clone()),
+                discarded: metrics::counter!(DISCARDED, "parser" => name.clone()),
+                errors: KINDS
+                    .into_iter()
+                    .map(|kind| {
+                        let counter =
+                            metrics::counter!(ERRORS, "parser" => name.clone(), "kind" => kind);
+                        (kind, counter)
+                    })
+                    .collect(),
+                heuristic_time: metrics::histogram!(HEURISTIC_TIME, "parser" => name.clone()),
+                parser_time: metrics::histogram!(PARSER_TIME, "parser" => name),
+                last_stage_end: Cell::new(None),
+            },
+        }
+    }
+
+    /// Record a parsed frame of `size` bytes
+    pub fn frame(&self, size: usize) {
+        #[cfg(feature = "metrics")]
+        {
+            self.handles.frames.increment(1);
+            self.handles.frame_size.record(size as f64);
+        }
+    }
+
+    /// Record `size` bytes skipped as noise by the heuristic
+    pub fn discarded(&self, size: usize) {
+        #[cfg(feature = "metrics")]
+        self.handles.discarded.increment(size as u64);
+    }
+
+    /// Record a yielded error, labelled by its variant
+    pub fn error(&self, err: &StreamParserError) {
+        #[cfg(feature = "metrics")]
+        if let Some(counter) = self.handles.errors.get(kind(err)) {
+            counter.increment(1);
+        }
+    }
+
+    /// Run a stage of the parsing, measuring its duration
+    ///
+    /// The stages of a parsing iteration are timed back to back, the clock
+    /// is read once per stage until [Metrics::iteration_end] is called.
+    pub fn time<T>(&self, stage: Stage, f: impl FnOnce() -> T) -> T {
+        #[cfg(feature = "metrics")]
+        {
+            let handles = &self.handles;
+            let start = handles.last_stage_end.take().unwrap_or_else(Instant::now);
+            let result = f();
+            let end = Instant::now();
+            handles.last_stage_end.set(Some(end));
+            let histogram = match stage {
+                Stage::Heuristic => &handles.heuristic_time,
+                Stage::Parser => &handles.parser_time,
+            };
+            histogram.record(end - start);
+            result
+        }
+        #[cfg(not(feature = "metrics"))]
+        f()
+    }
+
+    /// End a parsing iteration, the next stage timed doesn't follow the last one
+    pub fn iteration_end(&self) {
+        #[cfg(feature = "metrics")]
+        self.handles.last_stage_end.set(None);
+    }
+}
+
+/// Labels of the variants of the errors
+#[cfg(feature = "metrics")]
+const KINDS: [&str; 15] = [
+    "nom",
+    "io",
+    "exceeded_buffer",
+    "exceeded_buffer_unknown_size",
+    "pending",
+    "timeout",
+    "idle",
+    "frame_too_large",
+    "garbage_exceeded",
+    "too_many_errors",
+    "too_many_channels",
+    "duplicate_fragment",
+    "malformed_fragment",
+    "expired_message",
+    "layer",
+];
+
+/// Label of the variant of an error
+#[cfg(feature = "metrics")]
+fn kind(err: &StreamParserError) -> &'static str {
+    match err {
+        StreamParserError::Nom(_) => "nom",
+        StreamParserError::Io(_) => "io",
+        StreamParserError::ExceededBuffer { .. } => "exceeded_buffer",
+        StreamParserError::ExceededBufferUnknownSize { .. } => "exceeded_buffer_unknown_size",
+        StreamParserError::Pending => "pending",
+        StreamParserError::Timeout { .. } => "timeout",
+        StreamParserError::Idle { .. } => "idle",
+        StreamParserError::FrameTooLarge { .. } => "frame_too_large",
+        StreamParserError::GarbageExceeded { .. } => "garbage_exceeded",
+        StreamParserError::TooManyErrors { .. } => "too_many_errors",
+        StreamParserError::TooManyChannels { .. } => "too_many_channels",
+        StreamParserError::DuplicateFragment { .. } => "duplicate_fragment",
+        StreamParserError::MalformedFragment { .. } => "malformed_fragment",
+        StreamParserError::ExpiredMessage { .. } => "expired_message",
+        StreamParserError::Layer { .. } => "layer",
+    }
+}
+
+#[cfg(all(test, feature = "metrics"))]
+mod tests {
+    use std::collections::HashMap;
+
+    use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+
+    use utils::parsers::{parse_data, start_group_parenthesis};
+    use utils::source::Source;
+
+    use crate::buffers::preallocated::BufferPreallocated;
+    use crate::metrics::{DISCARDED, ERRORS, FRAMES, FRAME_SIZE, HEURISTIC_TIME, PARSER_TIME};
+    use crate::stream_parsers::sync_reader::StreamParser;
+    use crate::StartGroupByParser;
+
+    #[test_pretty_log::test]
+    fn test_metrics_labelled_by_buffer_name() {
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+
+        let stats = metrics::with_local_recorder(&recorder, || {
+            let data = b"##(1,2)ab(3)(x)";
+            let source = Source::new(data);
+            let mut work_buffer = BufferPreallocated::new(20).with_name("telemetry");
+            let heuristic = StartGroupByParser {
+                parser: start_group_parenthesis,
+                start_character: b"(",
+            };
+            let mut stream = StreamParser::new(source, &mut work_buffer, parse_data, heuristic);
+            stream.by_ref().for_each(drop);
+            stream.stats()
+        });
+
+        let metrics: HashMap<_, _> = snapshotter
+            .snapshot()
+            .into_vec()
+            .into_iter()
+            .map(|(key, _, _, value)| {
+                let (_, key) = key.into_parts();
+                let labels: Vec<_> = key
+                    .labels()
+                    .map(|label| format!("{}={}", label.key(), label.value()))
+                    .collect();
+                ((key.name().to_string(), labels.join(",")), value)
+            })
+            .collect();
+        let get = |name: &str, labels: &str| &metrics[&(name.to_string(), labels.to_string())];
+
+        assert_eq!(&DebugValue::Counter(2), get(FRAMES, "parser=telemetry"));
+        assert_eq!(
+            &DebugValue::Histogram(vec![5.0.into(), 3.0.into()]),
+            get(FRAME_SIZE, "parser=telemetry")
+        );
+        assert_eq!(
+            &DebugValue::Counter(stats.discarded),
+            get(DISCARDED, "parser=telemetry")
+        );
+        assert_eq!(
+            &DebugValue::Counter(1),
+            get(ERRORS, "parser=telemetry,kind=nom")
+        );
+        // The counters of every kind of error are registered with the parser
+        assert_eq!(
+            &DebugValue::Counter(0),
+            get(ERRORS, "parser=telemetry,kind=timeout")
+        );
+        for name in [HEURISTIC_TIME, PARSER_TIME] {
+            assert!(
+                matches!(get(name, "parser=telemetry"), DebugValue::Histogram(durations) if !durations.is_empty())
+            );
+        }
+    }
+}
@@ -1,5 +1,6 @@
 /// Define the decision of the master parser at previous iteration
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ParsableState {
     /// The data in the work aren't enough to decide the parsing state
     NeedMoreData,
@@ -8,7 +9,8 @@ pub enum ParsableState {
 }
 
 /// Command whether the search start group must be run
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SearchState {
     /// We are still searching for relevant data to parse
     SearchForStart,
@@ -0,0 +1,223 @@
+use std::io;
+use std::io::Read;
+use std::sync::mpsc;
+use std::time::Duration;
+
+/// Outcome of a receive attempt on a channel
+pub enum Received<C> {
+    /// A chunk of data has been received
+    Chunk(C),
+    /// No chunk has been received before the timeout
+    Timeout,
+    /// All senders have been dropped, no more chunk will be received
+    Closed,
+}
+
+/// The receiving half of a channel transmitting chunks of data
+pub trait ChunkReceiver {
+    type Chunk: AsRef<[u8]>;
+
+    /// Receive the next chunk, waiting at most `timeout` if defined
+    fn receive(&self, timeout: Option<Duration>) -> Received<Self::Chunk>;
+}
+
+impl<C: AsRef<[u8]>> ChunkReceiver for mpsc::Receiver<C> {
+    type Chunk = C;
+
+    fn receive(&self, timeout: Option<Duration>) -> Received<Self::Chunk> {
+        match timeout {
+            None => match self.recv() {
+                Ok(chunk) => Received::Chunk(chunk),
+                Err(mpsc::RecvError) => Received::Closed,
+            },
+            Some(timeout) => match self.recv_timeout(timeout) {
+                Ok(chunk) => Received::Chunk(chunk),
+                Err(mpsc::RecvTimeoutError::Timeout) => Received::Timeout,
+                Err(mpsc::RecvTimeoutError::Disconnected) => Received::Closed,
+            },
+        }
+    }
+}
+
+#[cfg(feature = "crossbeam")]
+impl<C: AsRef<[u8]>> ChunkReceiver for crossbeam_channel::Receiver<C> {
+    type Chunk = C;
+
+    fn receive(&self, timeout: Option<Duration>) -> Received<Self::Chunk> {
+        match timeout {
+            None => match self.recv() {
+                Ok(chunk) => Received::Chunk(chunk),
+                Err(crossbeam_channel::RecvError) => Received::Closed,
+            },
+            Some(timeout) => match self.recv_timeout(timeout) {
+                Ok(chunk) => Received::Chunk(chunk),
+                Err(crossbeam_channel::RecvTimeoutError::Timeout) => Received::Timeout,
+                Err(crossbeam_channel::RecvTimeoutError::Disconnected) => Received::Closed,
+            },
+        }
+    }
+}
+
+/// A reader over the chunks received from a channel
+///
+/// Chunks are owned by the source, so data produced on another thread
+/// can be parsed by [crate::stream_parsers::sync_reader::StreamParser].
+/// A closed channel ends the stream, and a receive timeout makes the stream
+/// parser yield [crate::StreamParserError::Pending].
+pub struct ChannelSource<R: ChunkReceiver> {
+    receiver: R,
+    /// Chunk partially copied into the work buffer
+    chunk: Option<R::Chunk>,
+    /// Amount of data of the current chunk already copied
+    position: usize,
+    timeout: Option<Duration>,
+}
+
+impl<R: ChunkReceiver> ChannelSource<R> {
+    pub fn new(receiver: R) -> Self {
+        ChannelSource {
+            receiver,
+            chunk: None,
+            position: 0,
+            timeout: None,
+        }
+    }
+
+    /// Define the maximum duration to wait for a chunk before giving back control
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+}
+
+impl<R: ChunkReceiver> Read for ChannelSource<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // An empty buffer means the work buffer is full, no chunk must be consumed
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        loop {
+            if let Some(chunk) = &self.chunk {
+                let remaining = &chunk.as_ref()[self.position..];
+                if !remaining.is_empty() {
+                    let size = remaining.len().min(buf.len());
+                    buf[..size].copy_from_slice(&remaining[..size]);
+                    self.position += size;
+                    return Ok(size);
+                }
+            }
+
+            match self.receiver.receive(self.timeout) {
+                Received::Chunk(chunk) => {
+                    self.chunk = Some(chunk);
+                    self.position = 0;
+                }
+                Received::Timeout => return Err(io::ErrorKind::WouldBlock.into()),
+                Received::Closed => return Ok(0),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+
+    use utils::parsers::{parse_data, start_group_parenthesis};
+
+    use crate::buffers::preallocated::BufferPreallocated;
+    use crate::sources::channel::ChannelSource;
+    use crate::stream_parsers::sync_reader::StreamParser;
+    use crate::{StartGroupByParser, StreamParserError};
+
+    #[test_pretty_log::test]
+    fn test_channel_source() {
+        let (sender, receiver) = mpsc::channel::<Vec<u8>>();
+
+        let producer = thread::spawn(move || {
+            for chunk in ["noise(1,2", "", ")(3,4)##(5", "6,7)(8)"] {
+                sender.send(chunk.as_bytes().to_vec()).unwrap();
+            }
+        });
+
+        let mut work_buffer = BufferPreallocated::new(20);
+        let heuristic = StartGroupByParser {
+            parser: start_group_parenthesis,
+            start_character: b"(",
+        };
+        let stream = StreamParser::new(
+            ChannelSource::new(receiver),
+            &mut work_buffer,
+            parse_data,
+            heuristic,
+        );
+
+        let result = stream.flatten().collect::<Vec<Vec<u8>>>();
+        producer.join().unwrap();
+
+        assert_eq!(vec![vec![1, 2], vec![3, 4], vec![56, 7], vec![8]], result);
+    }
+
+    #[cfg(feature = "crossbeam")]
+    #[test_pretty_log::test]
+    fn test_crossbeam_channel_source() {
+        use bytes::Bytes;
+
+        let (sender, receiver) = crossbeam_channel::unbounded::<Bytes>();
+
+        let producer = thread::spawn(move || {
+            for chunk in ["noise(1,2", "", ")(3,4)##(5", "6,7)(8)"] {
+                sender.send(Bytes::from_static(chunk.as_bytes())).unwrap();
+            }
+        });
+
+        let mut work_buffer = BufferPreallocated::new(20);
+        let heuristic = StartGroupByParser {
+            parser: start_group_parenthesis,
+            start_character: b"(",
+        };
+        let stream = StreamParser::new(
+            ChannelSource::new(receiver),
+            &mut work_buffer,
+            parse_data,
+            heuristic,
+        );
+
+        let result = stream.flatten().collect::<Vec<Vec<u8>>>();
+        producer.join().unwrap();
+
+        assert_eq!(vec![vec![1, 2], vec![3, 4], vec![56, 7], vec![8]], result);
+    }
+
+    #[test_pretty_log::test]
+    fn test_channel_source_timeout() {
+        let (sender, receiver) = mpsc::channel::<Vec<u8>>();
+
+        let mut work_buffer = BufferPreallocated::new(20);
+        let heuristic = StartGroupByParser {
+            parser: start_group_parenthesis,
+            start_character: b"(",
+        };
+        let source = ChannelSource::new(receiver).with_timeout(Duration::from_millis(1));
+        let mut stream = StreamParser::new(source, &mut work_buffer, parse_data, heuristic);
+
+        assert!(matches!(
+            stream.next(),
+            Some(Err(StreamParserError::Pending))
+        ));
+
+        sender.send(b"(1,".to_vec()).unwrap();
+        assert!(matches!(
+            stream.next(),
+            Some(Err(StreamParserError::Pending))
+        ));
+
+        sender.send(b"2)".to_vec()).unwrap();
+        drop(sender);
+        assert_eq!(Some(vec![1, 2]), stream.next().and_then(|x| x.ok()));
+        assert!(stream.next().is_none());
+    }
+}
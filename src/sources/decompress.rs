@@ -0,0 +1,291 @@
+use std::collections::VecDeque;
+use std::io;
+use std::io::{Chain, Cursor, Read};
+use std::ops::Range;
+
+use crate::trace::event;
+
+/// Length of the longest supported magic number
+const MAGIC_LEN: usize = 6;
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+const XZ_MAGIC: &[u8] = &[0xfd, 0x37, 0x7a, 0x58, 0x5a, 0x00];
+
+/// Number of reads whose compressed offset is kept
+const MAX_POSITIONS: usize = 1024;
+
+/// Compression format detected from the magic number of the data
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Compression {
+    /// No known magic number, data are read as is
+    None,
+    Gzip,
+    Zstd,
+    Xz,
+}
+
+/// Reader keeping track of the amount of data read
+struct Counting<R> {
+    inner: R,
+    count: u64,
+}
+
+impl<R: Read> Read for Counting<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let size = self.inner.read(buf)?;
+        self.count += size as u64;
+        Ok(size)
+    }
+}
+
+/// The compressed data, starting by the magic number read during detection
+type Compressed<R> = Chain<Cursor<Vec<u8>>, Counting<R>>;
+
+enum Decoder<R: Read> {
+    Raw(Compressed<R>),
+    #[cfg(feature = "gzip")]
+    Gzip(flate2::read::MultiGzDecoder<Compressed<R>>),
+    #[cfg(feature = "zstd")]
+    Zstd(zstd::stream::read::Decoder<'static, io::BufReader<Compressed<R>>>),
+    #[cfg(feature = "xz")]
+    Xz(xz2::read::XzDecoder<Compressed<R>>),
+}
+
+/// A reader decompressing its data, the compression format
+/// is detected from the magic number of the data
+///
+/// Gzip files made of several members and xz files made
+/// of several streams are read as a single stream.
+///
+/// The offsets of the stream parser, such as the ones of the checkpoints, are
+/// offsets of the decompressed data. They are mapped to the compressed data
+/// with [Decompress::compressed_position], for the data of the last reads.
+pub struct Decompress<R: Read> {
+    decoder: Decoder<R>,
+    compression: Compression,
+    /// Amount of decompressed data read
+    decompressed: u64,
+    /// Decompressed data and compressed offset reached by the last reads
+    positions: VecDeque<(Range<u64>, u64)>,
+}
+
+impl<R: Read> Decompress<R> {
+    pub fn new(reader: R) -> io::Result<Self> {
+        let mut reader = Counting {
+            inner: reader,
+            count: 0,
+        };
+
+        let mut magic = vec![0; MAGIC_LEN];
+        let mut magic_len = 0;
+        while magic_len < MAGIC_LEN {
+            match reader.read(&mut magic[magic_len..]) {
+                Ok(0) => break,
+                Ok(size) => magic_len += size,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+        magic.truncate(magic_len);
+
+        let compression = detect(&magic);
+        event!(debug, compression = ?compression, "Detected compression");
+
+        let compressed = Cursor::new(magic).chain(reader);
+        let decoder = match compression {
+            Compression::None => Decoder::Raw(compressed),
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => Decoder::Gzip(flate2::read::MultiGzDecoder::new(compressed)),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => Decoder::Zstd(zstd::stream::read::Decoder::new(compressed)?),
+            #[cfg(feature = "xz")]
+            Compression::Xz => Decoder::Xz(xz2::read::XzDecoder::new_multi_decoder(compressed)),
+            // Compressed data must not be parsed as is
+            #[cfg(not(all(feature = "gzip", feature = "zstd", feature = "xz")))]
+            compression => return Err(unsupported(compression)),
+        };
+
+        Ok(Decompress {
+            decoder,
+            compression,
+            decompressed: 0,
+            positions: VecDeque::new(),
+        })
+    }
+
+    /// Get the detected compression format
+    pub fn compression(&self) -> Compression {
+        self.compression
+    }
+
+    /// Get the amount of data read from the compressed source, decoders
+    /// read ahead so it may be greater than the data needed to decompress
+    /// the data read so far
+    // Without compression feature the raw decoder is the only variant
+    #[allow(clippy::infallible_destructuring_match)]
+    pub fn compressed_offset(&self) -> u64 {
+        let compressed = match &self.decoder {
+            Decoder::Raw(compressed) => compressed,
+            #[cfg(feature = "gzip")]
+            Decoder::Gzip(decoder) => decoder.get_ref(),
+            #[cfg(feature = "zstd")]
+            Decoder::Zstd(decoder) => decoder.get_ref().get_ref(),
+            #[cfg(feature = "xz")]
+            Decoder::Xz(decoder) => decoder.get_ref(),
+        };
+        compressed.get_ref().1.count
+    }
+
+    /// Get the amount of decompressed data read
+    pub fn decompressed_offset(&self) -> u64 {
+        self.decompressed
+    }
+
+    /// Get the amount of data read from the compressed source when the decompressed
+    /// data at `offset` were read, the data are compressed before this position
+    ///
+    /// Decoders read ahead, the position is exact up to the data read ahead.
+    /// Only the positions of the last reads are kept, `None` is returned for
+    /// older data and for data not read yet.
+    pub fn compressed_position(&self, offset: u64) -> Option<u64> {
+        let index = self
+            .positions
+            .partition_point(|(decompressed, _)| decompressed.end <= offset);
+        self.positions
+            .get(index)
+            .filter(|(decompressed, _)| decompressed.contains(&offset))
+            .map(|(_, compressed)| *compressed)
+    }
+}
+
+impl<R: Read> Read for Decompress<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let size = match &mut self.decoder {
+            Decoder::Raw(decoder) => decoder.read(buf),
+            #[cfg(feature = "gzip")]
+            Decoder::Gzip(decoder) => decoder.read(buf),
+            #[cfg(feature = "zstd")]
+            Decoder::Zstd(decoder) => decoder.read(buf),
+            #[cfg(feature = "xz")]
+            Decoder::Xz(decoder) => decoder.read(buf),
+        }?;
+
+        if size > 0 {
+            if self.positions.len() == MAX_POSITIONS {
+                self.positions.pop_front();
+            }
+            let decompressed = self.decompressed..self.decompressed + size as u64;
+            self.positions
+                .push_back((decompressed, self.compressed_offset()));
+        }
+        self.decompressed += size as u64;
+        Ok(size)
+    }
+}
+
+fn detect(magic: &[u8]) -> Compression {
+    if magic.starts_with(GZIP_MAGIC) {
+        Compression::Gzip
+    } else if magic.starts_with(ZSTD_MAGIC) {
+        Compression::Zstd
+    } else if magic.starts_with(XZ_MAGIC) {
+        Compression::Xz
+    } else {
+        Compression::None
+    }
+}
+
+/// Error returned when the data are compressed in a format whose feature is disabled
+#[cfg(not(all(feature = "gzip", feature = "zstd", feature = "xz")))]
+fn unsupported(compression: Compression) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("{compression:?} compressed data, the feature of the format is disabled"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(any(feature = "gzip", feature = "xz"))]
+    use std::io::Write;
+
+    use utils::parsers::{parse_data, start_group_parenthesis};
+
+    use crate::buffers::preallocated::BufferPreallocated;
+    use crate::sources::decompress::{Compression, Decompress};
+    use crate::stream_parsers::sync_reader::StreamParser;
+    use crate::StartGroupByParser;
+
+    const DATA: &[u8] = b"noise(1,2)(3,4)##(5,6,7)(8)";
+
+    fn parse(compressed: &[u8], compression: Compression) {
+        let source = Decompress::new(compressed).unwrap();
+        assert_eq!(compression, source.compression());
+
+        let mut work_buffer = BufferPreallocated::new(20);
+        let heuristic = StartGroupByParser {
+            parser: start_group_parenthesis,
+            start_character: b"(",
+        };
+        let mut stream = StreamParser::new(source, &mut work_buffer, parse_data, heuristic);
+
+        let mut result = vec![];
+        let mut positions = vec![];
+        while let Some(data) = stream.next() {
+            result.push(data.unwrap());
+            // Compressed position of the last byte of the frame
+            let offset = stream.checkpoint().offset - 1;
+            positions.push(stream.reader().compressed_position(offset).unwrap());
+        }
+        assert_eq!(vec![vec![1, 2], vec![3, 4], vec![5, 6, 7], vec![8]], result);
+
+        assert!(positions.is_sorted());
+        assert!(positions.iter().all(|&x| x <= compressed.len() as u64));
+
+        assert_eq!(DATA.len() as u64, stream.reader().decompressed_offset());
+        assert_eq!(None, stream.reader().compressed_position(DATA.len() as u64));
+        assert_eq!(compressed.len() as u64, stream.reader().compressed_offset());
+    }
+
+    #[test]
+    fn test_uncompressed() {
+        parse(DATA, Compression::None);
+    }
+
+    #[cfg(not(feature = "gzip"))]
+    #[test]
+    fn test_compression_disabled() {
+        let compressed = [0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00];
+        let error = Decompress::new(compressed.as_slice()).err().unwrap();
+        assert_eq!(std::io::ErrorKind::Unsupported, error.kind());
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_gzip_multi_member() {
+        let mut compressed = vec![];
+        for member in DATA.chunks(8) {
+            let mut encoder =
+                flate2::write::GzEncoder::new(&mut compressed, flate2::Compression::default());
+            encoder.write_all(member).unwrap();
+            encoder.finish().unwrap();
+        }
+        parse(&compressed, Compression::Gzip);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_zstd() {
+        let compressed = zstd::encode_all(DATA, 0).unwrap();
+        parse(&compressed, Compression::Zstd);
+    }
+
+    #[cfg(feature = "xz")]
+    #[test]
+    fn test_xz() {
+        let mut encoder = xz2::write::XzEncoder::new(vec![], 6);
+        encoder.write_all(DATA).unwrap();
+        parse(&encoder.finish().unwrap(), Compression::Xz);
+    }
+}
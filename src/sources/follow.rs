@@ -0,0 +1,194 @@
+use std::fs::{File, Metadata};
+use std::io;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use crate::sources::SourceEvent;
+use crate::trace::event;
+
+/// Default duration between two attempts to read a file which doesn't grow
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A reader following a growing file like `tail -F`
+///
+/// Reaching the end of the file doesn't end the stream, the reader waits
+/// for more data instead, so a partially written group stays in the work buffer
+/// until completed. When the file is truncated or replaced by another one
+/// (log rotation), the file is reopened and a [SourceEvent::Reset] is sent
+/// to the stream parser.
+pub struct Follow {
+    path: PathBuf,
+    file: File,
+    /// Identity of the opened file, used to detect rotation
+    identity: Option<u64>,
+    /// Number of bytes read from the opened file
+    position: u64,
+    poll_interval: Duration,
+    /// Wait for the file to grow, sleep for the poll interval by default
+    wait: Box<dyn FnMut(Duration) + Send>,
+    reset_heuristic: bool,
+}
+
+impl Follow {
+    /// Follow the file at `path` from its beginning
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = File::open(&path)?;
+        let identity = identity(&file.metadata()?);
+
+        Ok(Follow {
+            path,
+            file,
+            identity,
+            position: 0,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            wait: Box::new(thread::sleep),
+            reset_heuristic: false,
+        })
+    }
+
+    /// Define the duration waited before reading again a file which doesn't grow
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Define how to wait before reading again a file which doesn't grow,
+    /// the waiting function is given the poll interval
+    pub fn with_wait<W: FnMut(Duration) + Send + 'static>(mut self, wait: W) -> Self {
+        self.wait = Box::new(wait);
+        self
+    }
+
+    /// Define whether the heuristic state must be reset when the file is rotated
+    pub fn with_heuristic_reset(mut self, reset_heuristic: bool) -> Self {
+        self.reset_heuristic = reset_heuristic;
+        self
+    }
+
+    /// Detect whether the followed file has been truncated or rotated,
+    /// reopen it if needed
+    fn reopen_if_rotated(&mut self) -> io::Result<bool> {
+        let metadata = match std::fs::metadata(&self.path) {
+            Ok(metadata) => metadata,
+            // The file is being rotated, the new one doesn't exist yet
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(false),
+            Err(err) => return Err(err),
+        };
+
+        if identity(&metadata) != self.identity {
+            event!(debug, path = %self.path.display(), "Followed file rotated");
+            self.file = File::open(&self.path)?;
+            self.identity = identity(&self.file.metadata()?);
+            self.position = 0;
+            return Ok(true);
+        }
+
+        if metadata.len() < self.position {
+            event!(debug, path = %self.path.display(), "Followed file truncated");
+            self.file.seek(SeekFrom::Start(0))?;
+            self.position = 0;
+            return Ok(true);
+        }
+
+        Ok(false)
+    }
+}
+
+impl Read for Follow {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // An empty buffer means the work buffer is full, waiting won't help
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        loop {
+            let size = self.file.read(buf)?;
+            if size > 0 {
+                self.position += size as u64;
+                return Ok(size);
+            }
+
+            if self.reopen_if_rotated()? {
+                return Err(SourceEvent::Reset {
+                    heuristic: self.reset_heuristic,
+                }
+                .into_io_error());
+            }
+
+            event!(trace, path = %self.path.display(), "Waiting for the file to grow");
+            (self.wait)(self.poll_interval);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn identity(metadata: &Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.ino())
+}
+
+#[cfg(not(unix))]
+fn identity(_metadata: &Metadata) -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::OpenOptions;
+    use std::io::Write;
+
+    use utils::parsers::{parse_data, start_group_parenthesis};
+
+    use crate::buffers::preallocated::BufferPreallocated;
+    use crate::sources::follow::Follow;
+    use crate::stream_parsers::sync_reader::StreamParser;
+    use crate::StartGroupByParser;
+
+    fn append(path: &std::path::Path, data: &[u8]) {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .unwrap();
+        file.write_all(data).unwrap();
+    }
+
+    #[test_pretty_log::test]
+    fn test_follow_growing_and_rotated_file() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("device.log");
+        append(&path, b"(1,2)(3,");
+
+        // The file is written each time the reader waits for it to grow
+        let mut polls = 0;
+        let writer = {
+            let path = path.clone();
+            move |_| {
+                polls += 1;
+                match polls {
+                    1 => append(&path, b"4)(5)(6,"),
+                    2 => {
+                        std::fs::rename(&path, path.with_extension("log.1")).unwrap();
+                        append(&path, b"7)(8)");
+                    }
+                    _ => panic!("The file doesn't grow anymore"),
+                }
+            }
+        };
+
+        let source = Follow::open(&path).unwrap().with_wait(writer);
+        let mut work_buffer = BufferPreallocated::new(20);
+        let heuristic = StartGroupByParser {
+            parser: start_group_parenthesis,
+            start_character: b"(",
+        };
+        let stream = StreamParser::new(source, &mut work_buffer, parse_data, heuristic);
+
+        let result = stream.flatten().take(4).collect::<Vec<Vec<u8>>>();
+
+        assert_eq!(vec![vec![1, 2], vec![3, 4], vec![5], vec![8]], result);
+    }
+}
@@ -0,0 +1,45 @@
+use std::fmt::{Display, Formatter};
+use std::io;
+
+pub mod channel;
+pub mod decompress;
+pub mod follow;
+#[cfg(feature = "multi-file")]
+pub mod multi_file;
+pub mod trace;
+
+/// Out of band notification sent by a reader to the stream parser,
+/// carried by the [io::Error] returned by [io::Read::read]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SourceEvent {
+    /// The reader starts a new stream of data, data pending
+    /// in the work buffer can't be completed anymore and are dropped
+    Reset {
+        /// Whether the heuristic state must be reset too
+        heuristic: bool,
+    },
+}
+
+impl SourceEvent {
+    /// Wrap the event in an IO error to be returned by a reader
+    pub fn into_io_error(self) -> io::Error {
+        io::Error::other(self)
+    }
+
+    /// Extract the event from an IO error returned by a reader if any
+    pub fn from_io_error(error: &io::Error) -> Option<SourceEvent> {
+        error.get_ref()?.downcast_ref::<SourceEvent>().copied()
+    }
+}
+
+impl Display for SourceEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SourceEvent::Reset { heuristic } => {
+                write!(f, "Source reset, heuristic reset : {heuristic}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SourceEvent {}
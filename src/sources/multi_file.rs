@@ -0,0 +1,225 @@
+use std::fmt::Debug;
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::heuristic::Heuristic;
+use crate::sources::SourceEvent;
+use crate::stream_parsers::sync_reader::StreamParser;
+use crate::trace::event;
+use crate::{Buffer, ParserFunction, StreamParserError};
+
+/// Define how data are handled when the source switches from a file to the next one
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Boundary {
+    /// Files are segments of the same stream, a group may
+    /// start in a file and end in the next one
+    Continuous,
+    /// Files are independent, the work buffer and the heuristic
+    /// state are reset at each file
+    Independent,
+}
+
+/// A reader walking through an ordered list of files
+pub struct MultiFile {
+    files: Vec<Arc<Path>>,
+    boundary: Boundary,
+    /// Currently read file
+    current: Option<File>,
+    /// Index of the next file to open
+    next: usize,
+    /// Offset in the whole stream of the first byte of each opened file
+    starts: Vec<u64>,
+    /// Amount of data read from all files
+    position: u64,
+}
+
+impl MultiFile {
+    /// Read the files in the given order
+    pub fn new<P: AsRef<Path>>(files: impl IntoIterator<Item = P>) -> Self {
+        MultiFile {
+            files: files
+                .into_iter()
+                .map(|path| Arc::from(path.as_ref()))
+                .collect(),
+            boundary: Boundary::Continuous,
+            current: None,
+            next: 0,
+            starts: vec![],
+            position: 0,
+        }
+    }
+
+    /// Read the files matching a glob pattern in alphabetical order
+    pub fn glob(pattern: &str) -> io::Result<Self> {
+        let paths = glob::glob(pattern)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?
+            .collect::<Result<Vec<PathBuf>, _>>()
+            .map_err(io::Error::from)?;
+
+        Ok(Self::new(paths))
+    }
+
+    /// Define how data are handled between two files
+    pub fn with_boundary(mut self, boundary: Boundary) -> Self {
+        self.boundary = boundary;
+        self
+    }
+
+    /// Get the file containing the data at the given offset of the whole stream
+    pub fn file_at(&self, offset: u64) -> Option<&Arc<Path>> {
+        let index = self.starts.partition_point(|start| *start <= offset);
+        self.files.get(index.checked_sub(1)?)
+    }
+
+    fn open_next(&mut self) -> io::Result<()> {
+        let path = &self.files[self.next];
+        event!(debug, path = %path.display(), "Opening file");
+        self.current = Some(File::open(path)?);
+        self.starts.push(self.position);
+        self.next += 1;
+        Ok(())
+    }
+}
+
+impl Read for MultiFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        loop {
+            if let Some(file) = self.current.as_mut() {
+                let size = file.read(buf)?;
+                if size > 0 {
+                    self.position += size as u64;
+                    return Ok(size);
+                }
+                self.current = None;
+
+                if self.boundary == Boundary::Independent && self.next < self.files.len() {
+                    self.open_next()?;
+                    return Err(SourceEvent::Reset { heuristic: true }.into_io_error());
+                }
+            }
+
+            if self.next >= self.files.len() {
+                return Ok(0);
+            }
+            self.open_next()?;
+        }
+    }
+}
+
+/// Stream parser over a [MultiFile] yielding each data
+/// along with the file containing its last byte
+pub struct TaggedStreamParser<'a, B, O, H>
+where
+    B: Buffer,
+    H: Heuristic,
+    O: Debug,
+{
+    stream: StreamParser<'a, MultiFile, B, O, H>,
+}
+
+impl<'a, B, O, H> TaggedStreamParser<'a, B, O, H>
+where
+    B: Buffer,
+    H: Heuristic,
+    O: Debug,
+{
+    pub fn new(
+        source: MultiFile,
+        work_buffer: &'a mut B,
+        parser: ParserFunction<O>,
+        heuristic: H,
+    ) -> Self {
+        TaggedStreamParser {
+            stream: StreamParser::new(source, work_buffer, parser, heuristic),
+        }
+    }
+}
+
+impl<'a, B, O, H> Iterator for TaggedStreamParser<'a, B, O, H>
+where
+    B: Buffer,
+    H: Heuristic,
+    O: Debug,
+{
+    type Item = (Option<Arc<Path>>, Result<O, StreamParserError>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.stream.next()?;
+        let end = self.stream.checkpoint().offset.saturating_sub(1);
+        let file = self.stream.reader().file_at(end).cloned();
+        Some((file, item))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use utils::parsers::{parse_data, start_group_parenthesis};
+
+    use crate::buffers::preallocated::BufferPreallocated;
+    use crate::sources::multi_file::{Boundary, MultiFile, TaggedStreamParser};
+    use crate::StartGroupByParser;
+
+    fn parse(source: MultiFile) -> Vec<(String, Vec<u8>)> {
+        let mut work_buffer = BufferPreallocated::new(20);
+        let heuristic = StartGroupByParser {
+            parser: start_group_parenthesis,
+            start_character: b"(",
+        };
+        TaggedStreamParser::new(source, &mut work_buffer, parse_data, heuristic)
+            .filter_map(|(file, data)| {
+                let file = file?.file_name()?.to_string_lossy().to_string();
+                Some((file, data.ok()?))
+            })
+            .collect()
+    }
+
+    fn segments(directory: &Path) {
+        std::fs::write(directory.join("dump.000"), b"(1,2)(3,").unwrap();
+        std::fs::write(directory.join("dump.001"), b"").unwrap();
+        std::fs::write(directory.join("dump.002"), b"4)(5)").unwrap();
+    }
+
+    #[test_pretty_log::test]
+    fn test_multi_file_continuous() {
+        let directory = tempfile::tempdir().unwrap();
+        segments(directory.path());
+        let pattern = directory.path().join("dump.*");
+
+        let source = MultiFile::glob(pattern.to_str().unwrap()).unwrap();
+
+        assert_eq!(
+            vec![
+                ("dump.000".to_string(), vec![1, 2]),
+                ("dump.002".to_string(), vec![3, 4]),
+                ("dump.002".to_string(), vec![5]),
+            ],
+            parse(source)
+        );
+    }
+
+    #[test_pretty_log::test]
+    fn test_multi_file_independent() {
+        let directory = tempfile::tempdir().unwrap();
+        segments(directory.path());
+        let files = ["dump.000", "dump.001", "dump.002"].map(|x| directory.path().join(x));
+
+        let source = MultiFile::new(files).with_boundary(Boundary::Independent);
+
+        assert_eq!(
+            vec![
+                ("dump.000".to_string(), vec![1, 2]),
+                ("dump.002".to_string(), vec![5]),
+            ],
+            parse(source)
+        );
+    }
+}
@@ -0,0 +1,323 @@
+use std::fs::File;
+use std::io;
+use std::io::{BufReader, Read, Write};
+use std::path::Path;
+
+/// Header of a trace file, followed by the version of the format
+const MAGIC: &[u8; 4] = b"NSPT";
+const VERSION: u8 = 1;
+
+/// Source wrapper recording the exact chunks it yields into a trace,
+/// to be replayed later with the same chunk boundaries by a [Trace]
+///
+/// The trace is made of a header then each chunk, prefixed by its
+/// length as a LEB128 variable length integer. It is complete once
+/// the recorder is finished or dropped along with a writer flushing
+/// itself. Errors of a reader, including source events, aren't recorded.
+pub struct Recorder<S, W: Write> {
+    source: S,
+    writer: W,
+    /// First error met while writing the trace of an iterator source
+    error: Option<io::Error>,
+    header: bool,
+}
+
+impl<S, W: Write> Recorder<S, W> {
+    /// Record the chunks of the source into the writer,
+    /// preferably buffered as each chunk is written separately
+    pub fn new(source: S, writer: W) -> Self {
+        Recorder {
+            source,
+            writer,
+            error: None,
+            header: false,
+        }
+    }
+
+    /// Flush the trace and release the writer, the first error
+    /// met while recording an iterator source is returned if any
+    pub fn finish(mut self) -> io::Result<W> {
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+        self.write_header()?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    fn write_header(&mut self) -> io::Result<()> {
+        if !self.header {
+            self.writer.write_all(MAGIC)?;
+            self.writer.write_all(&[VERSION])?;
+            self.header = true;
+        }
+        Ok(())
+    }
+
+    fn record(&mut self, chunk: &[u8]) -> io::Result<()> {
+        self.write_header()?;
+        let mut len = chunk.len();
+        loop {
+            let byte = (len & 0x7f) as u8;
+            len >>= 7;
+            if len == 0 {
+                self.writer.write_all(&[byte])?;
+                break;
+            }
+            self.writer.write_all(&[byte | 0x80])?;
+        }
+        self.writer.write_all(chunk)
+    }
+}
+
+impl<'a, I, W> Iterator for Recorder<I, W>
+where
+    I: Iterator<Item = &'a [u8]>,
+    W: Write,
+{
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let chunk = self.source.next()?;
+        if self.error.is_none() {
+            if let Err(err) = self.record(chunk) {
+                self.error = Some(err);
+            }
+        }
+        Some(chunk)
+    }
+}
+
+impl<R: Read, W: Write> Read for Recorder<R, W> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let size = self.source.read(buf)?;
+        if size > 0 {
+            self.record(&buf[..size])?;
+        }
+        Ok(size)
+    }
+}
+
+/// Chunks recorded by a [Recorder], replayed with their original boundaries
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trace {
+    data: Vec<u8>,
+    /// End of each chunk in data
+    ends: Vec<usize>,
+}
+
+impl Trace {
+    /// Load the trace recorded in a file
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Trace> {
+        Trace::read_from(BufReader::new(File::open(path)?))
+    }
+
+    /// Load a recorded trace
+    pub fn read_from<R: Read>(mut reader: R) -> io::Result<Trace> {
+        let mut bytes = vec![];
+        reader.read_to_end(&mut bytes)?;
+        Trace::from_bytes(&bytes)
+    }
+
+    /// Decode a recorded trace, nothing is recorded by a
+    /// recorder dropped before yielding any chunk
+    pub fn from_bytes(mut bytes: &[u8]) -> io::Result<Trace> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message);
+
+        match bytes.split_first_chunk::<4>() {
+            _ if bytes.is_empty() => {}
+            Some((magic, [VERSION, remain @ ..])) if magic == MAGIC => bytes = remain,
+            Some((magic, [version, ..])) if magic == MAGIC => {
+                return Err(invalid(&format!("unsupported trace version {version}")))
+            }
+            _ => return Err(invalid("not a trace")),
+        }
+
+        let mut trace = Trace {
+            data: Vec::with_capacity(bytes.len()),
+            ends: vec![],
+        };
+        while !bytes.is_empty() {
+            let mut len = 0usize;
+            let mut shift = 0;
+            loop {
+                let (&byte, remain) = bytes
+                    .split_first()
+                    .ok_or_else(|| invalid("truncated chunk length"))?;
+                bytes = remain;
+                len |= ((byte & 0x7f) as usize)
+                    .checked_shl(shift)
+                    .ok_or_else(|| invalid("chunk length overflow"))?;
+                shift += 7;
+                if byte & 0x80 == 0 {
+                    break;
+                }
+            }
+            if len > bytes.len() {
+                return Err(invalid("truncated chunk"));
+            }
+            trace.data.extend_from_slice(&bytes[..len]);
+            trace.ends.push(trace.data.len());
+            bytes = &bytes[len..];
+        }
+        Ok(trace)
+    }
+
+    /// Iterate over the recorded chunks, to be parsed by a
+    /// [sync_iterator::StreamParser](crate::stream_parsers::sync_iterator::StreamParser)
+    pub fn chunks(&self) -> Chunks<'_> {
+        Chunks {
+            trace: self,
+            index: 0,
+        }
+    }
+
+    /// Read the recorded chunks, each read returns at most one chunk
+    pub fn reader(&self) -> Replay<'_> {
+        Replay {
+            chunks: self.chunks(),
+            pending: &[],
+        }
+    }
+
+    /// Get all the recorded data
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+/// Iterator over the chunks of a [Trace]
+pub struct Chunks<'a> {
+    trace: &'a Trace,
+    index: usize,
+}
+
+impl<'a> Iterator for Chunks<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let end = *self.trace.ends.get(self.index)?;
+        let start = match self.index {
+            0 => 0,
+            index => self.trace.ends[index - 1],
+        };
+        self.index += 1;
+        Some(&self.trace.data[start..end])
+    }
+}
+
+/// Reader over the chunks of a [Trace], a chunk larger than
+/// the read buffer is split over several reads
+pub struct Replay<'a> {
+    chunks: Chunks<'a>,
+    pending: &'a [u8],
+}
+
+impl Read for Replay<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pending.is_empty() {
+            match self.chunks.next() {
+                Some(chunk) => self.pending = chunk,
+                None => return Ok(0),
+            }
+        }
+        let size = buf.len().min(self.pending.len());
+        buf[..size].copy_from_slice(&self.pending[..size]);
+        self.pending = &self.pending[size..];
+        Ok(size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{ErrorKind, Read};
+
+    use utils::parsers::{parse_data, start_group_parenthesis};
+    use utils::source::Source;
+
+    use crate::buffers::preallocated::BufferPreallocated;
+    use crate::sources::trace::{Recorder, Trace};
+    use crate::stream_parsers::{sync_iterator, sync_reader};
+    use crate::StartGroupByParser;
+
+    const DATA: &[u8] = b"(1,2)ab(3,4,5)(6";
+
+    fn heuristic() -> StartGroupByParser<'static> {
+        StartGroupByParser {
+            parser: start_group_parenthesis,
+            start_character: b"(",
+        }
+    }
+
+    #[test_pretty_log::test]
+    fn test_record_and_replay_chunks() {
+        let mut bytes = vec![];
+        let mut work_buffer = BufferPreallocated::new(20);
+        let recorder = Recorder::new(Source::new(DATA).with_chunk_size(3), &mut bytes);
+        let expected: Vec<_> =
+            sync_iterator::StreamParser::new(recorder, &mut work_buffer, parse_data, heuristic())
+                .map(Result::ok)
+                .collect();
+
+        let trace = Trace::from_bytes(&bytes).unwrap();
+        assert_eq!(
+            Source::new(DATA).with_chunk_size(3).collect::<Vec<_>>(),
+            trace.chunks().collect::<Vec<_>>()
+        );
+
+        let mut work_buffer = BufferPreallocated::new(20);
+        let replayed: Vec<_> = sync_iterator::StreamParser::new(
+            trace.chunks(),
+            &mut work_buffer,
+            parse_data,
+            heuristic(),
+        )
+        .map(Result::ok)
+        .collect();
+        assert_eq!(expected, replayed);
+    }
+
+    #[test_pretty_log::test]
+    fn test_record_reader() {
+        let mut recorder = Recorder::new(DATA, vec![]);
+        let mut work_buffer = BufferPreallocated::new(5);
+        let parsed: Vec<_> = sync_reader::StreamParser::new(
+            &mut recorder,
+            &mut work_buffer,
+            parse_data,
+            heuristic(),
+        )
+        .map(Result::ok)
+        .collect();
+
+        let trace = Trace::from_bytes(&recorder.finish().unwrap()).unwrap();
+        assert_eq!(DATA, trace.data());
+        assert!(trace.chunks().all(|chunk| chunk.len() <= 5));
+
+        let mut work_buffer = BufferPreallocated::new(5);
+        let replayed: Vec<_> = sync_reader::StreamParser::new(
+            trace.reader(),
+            &mut work_buffer,
+            parse_data,
+            heuristic(),
+        )
+        .map(Result::ok)
+        .collect();
+        assert_eq!(parsed, replayed);
+
+        let mut replay = trace.reader();
+        let mut data = vec![];
+        replay.read_to_end(&mut data).unwrap();
+        assert_eq!(DATA, data);
+    }
+
+    #[test]
+    fn test_invalid_trace() {
+        for bytes in [&b"NSP"[..], b"NSPT\x02", b"NSPT\x01\x05ab", b"NSPT\x01\x80"] {
+            let err = Trace::from_bytes(bytes).unwrap_err();
+            assert_eq!(ErrorKind::InvalidData, err.kind());
+        }
+        assert_eq!(0, Trace::from_bytes(b"NSPT\x01").unwrap().chunks().count());
+        assert_eq!(0, Trace::from_bytes(b"").unwrap().chunks().count());
+    }
+}
@@ -0,0 +1,62 @@
+/// Counters of the activity of a stream parser
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Stats {
+    /// Amount of data read from the source
+    pub bytes_read: u64,
+    /// Number of data yielded
+    pub frames: u64,
+    /// Number of parsing errors
+    pub errors: u64,
+    /// Amount of data discarded by the heuristic while searching a group start
+    pub discarded: u64,
+    /// Number of evictions of parsed data from the work buffer
+    pub evictions: u64,
+    /// Maximum amount of data held by the work buffer
+    pub high_water_mark: usize,
+    /// Number of times the parser needed more data to take a decision
+    pub incomplete: u64,
+}
+
+/// An event of the activity of a stream parser, as counted by [Stats]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// Data were read from the source
+    Read { size: usize },
+    /// A data was parsed
+    Frame,
+    /// A parsing error occurred
+    Error,
+    /// Data were discarded by the heuristic
+    Discarded { size: usize },
+    /// Parsed data were evinced from the work buffer
+    Eviction { size: usize },
+    /// The work buffer holds more data than ever
+    HighWaterMark { size: usize },
+    /// The parser needs more data to take a decision
+    Incomplete,
+}
+
+/// Receive the events of a stream parser as they happen
+pub trait Observer {
+    fn observe(&mut self, event: &Event);
+}
+
+impl<F: FnMut(&Event)> Observer for F {
+    fn observe(&mut self, event: &Event) {
+        self(event)
+    }
+}
+
+impl Stats {
+    pub(crate) fn record(&mut self, event: &Event) {
+        match event {
+            Event::Read { size } => self.bytes_read += *size as u64,
+            Event::Frame => self.frames += 1,
+            Event::Error => self.errors += 1,
+            Event::Discarded { size } => self.discarded += *size as u64,
+            Event::Eviction { .. } => self.evictions += 1,
+            Event::HighWaterMark { size } => self.high_water_mark = *size,
+            Event::Incomplete => self.incomplete += 1,
+        }
+    }
+}
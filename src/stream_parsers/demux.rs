@@ -0,0 +1,247 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use crate::heuristic::Heuristic;
+use crate::logic::parse_internal;
+use crate::parser_state::{ParsableState, SearchState};
+use crate::trace::event;
+use crate::{Buffer, ParserFunction, StreamParserError};
+
+/// Reassembly state of a logical channel
+struct Channel<B, H> {
+    work_buffer: B,
+    state: (SearchState, ParsableState),
+    cursor: usize,
+    heuristic: H,
+}
+
+impl<B: Buffer, H: Heuristic> Channel<B, H> {
+    fn new(work_buffer: B, heuristic: H) -> Self {
+        Channel {
+            work_buffer,
+            state: (SearchState::SearchForStart, ParsableState::NeedMoreData),
+            cursor: 0,
+            heuristic,
+        }
+    }
+
+    /// Append a payload to the channel and parse every data completed by it
+    fn feed<O: Debug>(
+        &mut self,
+        payload: &[u8],
+        parser: &mut ParserFunction<O>,
+        parsed: &mut VecDeque<Result<O, StreamParserError>>,
+    ) {
+        match self.work_buffer.append(payload, Some(self.cursor)) {
+            Err(err) => {
+                event!(debug, "Channel buffer overflow, dropping pending data");
+                self.work_buffer.reset();
+                self.cursor = 0;
+                self.state = (SearchState::SearchForStart, ParsableState::NeedMoreData);
+                parsed.push_back(Err(err));
+                return;
+            }
+            Ok(true) => self.cursor = 0,
+            Ok(false) => {}
+        }
+        self.state.1 = ParsableState::MaybeParsable;
+
+        while let ParsableState::MaybeParsable = self.state.1 {
+            match parse_internal(
+                &mut self.work_buffer,
+                &mut self.state,
+                &mut self.cursor,
+                parser,
+                &mut self.heuristic,
+            ) {
+                Ok(Some(data)) => parsed.push_back(Ok(data)),
+                Ok(None) => {}
+                Err(err) => parsed.push_back(Err(err)),
+            }
+        }
+    }
+}
+
+/// Demultiplex the frames of a link carrying several logical channels
+///
+/// Each frame is split in a channel key and a payload, payloads of a channel
+/// are reassembled in their own work buffer with their own heuristic, then parsed.
+/// The data are yielded along with their channel key, errors of the link have no key.
+pub struct Demux<S, F, K, T, B, O, H>
+where
+    S: Iterator<Item = Result<F, StreamParserError>>,
+    K: Hash + Eq + Clone,
+    T: AsRef<[u8]>,
+    B: Buffer,
+    H: Heuristic + Clone,
+    O: Debug,
+{
+    frames: S,
+    split: fn(F) -> (K, T),
+    parser: ParserFunction<O>,
+    heuristic: H,
+    /// Create the work buffer of a new channel, its size bounds the channel memory
+    new_buffer: Box<dyn Fn() -> B>,
+    channels: HashMap<K, Channel<B, H>>,
+    max_channels: Option<usize>,
+    /// Data parsed but not yielded yet
+    pending: VecDeque<(Option<K>, Result<O, StreamParserError>)>,
+}
+
+impl<S, F, K, T, B, O, H> Demux<S, F, K, T, B, O, H>
+where
+    S: Iterator<Item = Result<F, StreamParserError>>,
+    K: Hash + Eq + Clone,
+    T: AsRef<[u8]>,
+    B: Buffer,
+    H: Heuristic + Clone,
+    O: Debug,
+{
+    pub fn new(
+        frames: S,
+        split: fn(F) -> (K, T),
+        new_buffer: impl Fn() -> B + 'static,
+        parser: ParserFunction<O>,
+        heuristic: H,
+    ) -> Self {
+        Demux {
+            frames,
+            split,
+            parser,
+            heuristic,
+            new_buffer: Box::new(new_buffer),
+            channels: HashMap::new(),
+            max_channels: None,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Define the maximum number of channels, frames of
+    /// additional channels are dropped with an error
+    pub fn with_max_channels(mut self, max_channels: usize) -> Self {
+        self.max_channels = Some(max_channels);
+        self
+    }
+
+    /// Forget a channel and the data pending in its work buffer
+    pub fn remove_channel(&mut self, key: &K) {
+        self.channels.remove(key);
+    }
+
+    /// Get the number of channels currently demultiplexed
+    pub fn channels(&self) -> usize {
+        self.channels.len()
+    }
+}
+
+impl<S, F, K, T, B, O, H> Iterator for Demux<S, F, K, T, B, O, H>
+where
+    S: Iterator<Item = Result<F, StreamParserError>>,
+    K: Hash + Eq + Clone,
+    T: AsRef<[u8]>,
+    B: Buffer,
+    H: Heuristic + Clone,
+    O: Debug,
+{
+    type Item = (Option<K>, Result<O, StreamParserError>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.pending.pop_front() {
+                return Some(item);
+            }
+
+            let frame = match self.frames.next()? {
+                Ok(frame) => frame,
+                Err(err) => return Some((None, Err(err))),
+            };
+            let (key, payload) = (self.split)(frame);
+
+            if !self.channels.contains_key(&key) {
+                if let Some(max_channels) = self.max_channels {
+                    if self.channels.len() >= max_channels {
+                        return Some((
+                            Some(key),
+                            Err(StreamParserError::TooManyChannels { max_channels }),
+                        ));
+                    }
+                }
+                let channel = Channel::new((self.new_buffer)(), self.heuristic.clone());
+                self.channels.insert(key.clone(), channel);
+            }
+
+            let mut parsed = VecDeque::new();
+            if let Some(channel) = self.channels.get_mut(&key) {
+                channel.feed(payload.as_ref(), &mut self.parser, &mut parsed);
+            }
+            self.pending
+                .extend(parsed.into_iter().map(|data| (Some(key.clone()), data)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nom::bytes::streaming::{tag, take, take_until};
+    use nom::sequence::{terminated, tuple};
+    use nom::IResult;
+
+    use utils::parsers::{parse_data, start_group_parenthesis};
+    use utils::source::Source;
+
+    use crate::buffers::preallocated::BufferPreallocated;
+    use crate::heuristic::Increment;
+    use crate::stream_parsers::demux::Demux;
+    use crate::stream_parsers::sync_iterator;
+    use crate::{StartGroupByParser, StreamParserError};
+
+    fn parse_link(input: &[u8]) -> IResult<&[u8], (u8, Vec<u8>)> {
+        let (remain, (channel, _, payload)) = tuple((
+            take(1_usize),
+            tag("="),
+            terminated(take_until(";"), tag(";")),
+        ))(input)?;
+        Ok((remain, (channel[0], payload.to_vec())))
+    }
+
+    #[test_pretty_log::test]
+    fn test_demux() {
+        let data = b"a=#(1,;b=(7);a=2);c=(9);b=(8,8);a=(123456789);";
+        let mut link_buffer = BufferPreallocated::new(40);
+        let link = sync_iterator::StreamParser::new(
+            Source::new(data),
+            &mut link_buffer,
+            parse_link,
+            Increment,
+        );
+
+        let heuristic = StartGroupByParser {
+            parser: start_group_parenthesis,
+            start_character: b"(",
+        };
+        let demux = Demux::new(
+            link,
+            |frame| frame,
+            || BufferPreallocated::new(8),
+            parse_data,
+            heuristic,
+        )
+        .with_max_channels(2);
+
+        let result = demux
+            .map(|(key, data)| (key.map(char::from), data))
+            .collect::<Vec<_>>();
+
+        assert!(matches!(
+            &result[..],
+            [
+                (Some('b'), Ok(b)),
+                (Some('a'), Ok(a)),
+                (Some('c'), Err(StreamParserError::TooManyChannels { max_channels: 2 })),
+                (Some('b'), Ok(b2)),
+                (Some('a'), Err(StreamParserError::ExceededBuffer { .. })),
+            ] if b == &vec![7] && a == &vec![1, 2] && b2 == &vec![8, 8]
+        ));
+    }
+}
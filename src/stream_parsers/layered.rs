@@ -0,0 +1,269 @@
+use std::fmt::Debug;
+use std::io;
+use std::io::Read;
+
+use crate::heuristic::Heuristic;
+use crate::stream_parsers::sync_iterator;
+use crate::stream_parsers::sync_reader::{self, StreamParser};
+use crate::traits::Parse;
+use crate::{Buffer, ParserFunction, StreamParserError};
+
+/// A reader over the payloads yielded by a lower layer, typically another stream parser
+///
+/// Errors of the lower layer are forwarded through the [io::Error] returned by the reader.
+pub struct Payloads<I, T>
+where
+    I: Iterator<Item = Result<T, StreamParserError>>,
+    T: AsRef<[u8]>,
+{
+    lower: I,
+    /// Payload partially copied into the work buffer
+    payload: Option<T>,
+    /// Amount of data of the current payload already copied
+    cursor: usize,
+    /// Amount of payload data read
+    position: u64,
+}
+
+impl<I, T> Payloads<I, T>
+where
+    I: Iterator<Item = Result<T, StreamParserError>>,
+    T: AsRef<[u8]>,
+{
+    pub fn new(lower: I) -> Self {
+        Payloads {
+            lower,
+            payload: None,
+            cursor: 0,
+            position: 0,
+        }
+    }
+
+    /// Get the amount of payload data read
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Get the lower layer
+    pub fn lower(&self) -> &I {
+        &self.lower
+    }
+}
+
+impl<I, T> Read for Payloads<I, T>
+where
+    I: Iterator<Item = Result<T, StreamParserError>>,
+    T: AsRef<[u8]>,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        loop {
+            if let Some(payload) = &self.payload {
+                let remaining = &payload.as_ref()[self.cursor..];
+                if !remaining.is_empty() {
+                    let size = remaining.len().min(buf.len());
+                    buf[..size].copy_from_slice(&remaining[..size]);
+                    self.cursor += size;
+                    self.position += size as u64;
+                    return Ok(size);
+                }
+            }
+
+            match self.lower.next() {
+                Some(Ok(payload)) => {
+                    self.payload = Some(payload);
+                    self.cursor = 0;
+                }
+                Some(Err(StreamParserError::Pending)) => {
+                    return Err(io::ErrorKind::WouldBlock.into())
+                }
+                Some(Err(err)) => return Err(io::Error::other(err)),
+                None => return Ok(0),
+            }
+        }
+    }
+}
+
+/// A lower layer knowing the offset reached in its own source
+pub trait Lower {
+    /// Offset in the source of the lower layer of the next data to parse
+    fn offset(&self) -> u64;
+}
+
+impl<'a, I, B, O, H> Lower for sync_iterator::StreamParser<'a, I, B, O, H>
+where
+    I: Iterator<Item = &'a [u8]>,
+    B: Buffer,
+    H: Heuristic,
+    O: Debug,
+{
+    fn offset(&self) -> u64 {
+        self.checkpoint().offset
+    }
+}
+
+impl<R, B, O, H, P> Lower for sync_reader::StreamParser<'_, R, B, O, H, P>
+where
+    R: Read,
+    B: Buffer,
+    H: Heuristic,
+    O: Debug,
+    P: Parse<O>,
+{
+    fn offset(&self) -> u64 {
+        self.checkpoint().offset
+    }
+}
+
+/// Lower layer remembering where the data of its last item start
+struct Tracked<I> {
+    lower: I,
+    /// Offset in the source of the lower layer before yielding the last item
+    start: u64,
+}
+
+impl<I: Iterator + Lower> Iterator for Tracked<I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.start = self.lower.offset();
+        self.lower.next()
+    }
+}
+
+/// Stream parser whose data are the payloads yielded by a lower layer
+///
+/// Errors are reported as [StreamParserError::Layer], layer 0 being the lower
+/// layer and layer 1 this stream parser. The offset of a lower layer error is
+/// the offset in the source of the lower layer where the failing data start,
+/// the offset of an error of this stream parser is the offset in the payloads.
+pub struct LayeredStreamParser<'a, I, T, B, O, H>
+where
+    I: Iterator<Item = Result<T, StreamParserError>> + Lower,
+    T: AsRef<[u8]>,
+    B: Buffer,
+    H: Heuristic,
+    O: Debug,
+{
+    stream: StreamParser<'a, Payloads<Tracked<I>, T>, B, O, H>,
+}
+
+impl<'a, I, T, B, O, H> LayeredStreamParser<'a, I, T, B, O, H>
+where
+    I: Iterator<Item = Result<T, StreamParserError>> + Lower,
+    T: AsRef<[u8]>,
+    B: Buffer,
+    H: Heuristic,
+    O: Debug,
+{
+    pub fn new(lower: I, work_buffer: &'a mut B, parser: ParserFunction<O>, heuristic: H) -> Self {
+        LayeredStreamParser {
+            stream: StreamParser::new(
+                Payloads::new(Tracked { lower, start: 0 }),
+                work_buffer,
+                parser,
+                heuristic,
+            ),
+        }
+    }
+}
+
+impl<'a, I, T, B, O, H> Iterator for LayeredStreamParser<'a, I, T, B, O, H>
+where
+    I: Iterator<Item = Result<T, StreamParserError>> + Lower,
+    T: AsRef<[u8]>,
+    B: Buffer,
+    H: Heuristic,
+    O: Debug,
+{
+    type Item = Result<O, StreamParserError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let error = match self.stream.next()? {
+            Ok(data) => return Some(Ok(data)),
+            Err(StreamParserError::Pending) => return Some(Err(StreamParserError::Pending)),
+            Err(err) => err,
+        };
+
+        let error = match error {
+            StreamParserError::Io(err) if is_lower_error(&err) => StreamParserError::Layer {
+                layer: 0,
+                offset: self.stream.reader().lower().start,
+                source: err
+                    .into_inner()
+                    .and_then(|err| err.downcast::<StreamParserError>().ok())
+                    .expect("lower layer error"),
+            },
+            err => StreamParserError::Layer {
+                layer: 1,
+                offset: self.stream.checkpoint().offset,
+                source: Box::new(err),
+            },
+        };
+
+        Some(Err(error))
+    }
+}
+
+fn is_lower_error(error: &io::Error) -> bool {
+    error
+        .get_ref()
+        .is_some_and(|err| err.is::<StreamParserError>())
+}
+
+#[cfg(test)]
+mod tests {
+    use nom::bytes::streaming::{tag, take_until};
+    use nom::sequence::delimited;
+    use nom::IResult;
+
+    use utils::parsers::{parse_data, start_group_parenthesis};
+    use utils::source::Source;
+
+    use crate::buffers::preallocated::BufferPreallocated;
+    use crate::heuristic::Increment;
+    use crate::stream_parsers::layered::LayeredStreamParser;
+    use crate::stream_parsers::sync_iterator;
+    use crate::{StartGroupByParser, StreamParserError};
+
+    fn parse_transport(input: &[u8]) -> IResult<&[u8], Vec<u8>> {
+        let (remain, payload) = delimited(tag("["), take_until("]"), tag("]"))(input)?;
+        Ok((remain, payload.to_vec()))
+    }
+
+    #[test_pretty_log::test]
+    fn test_layered_stream_parser() {
+        let data = b"[noise(1,][2)(3][,4)#]x[(5)]";
+        let mut transport_buffer = BufferPreallocated::new(20);
+        let transport = sync_iterator::StreamParser::new(
+            Source::new(data),
+            &mut transport_buffer,
+            parse_transport,
+            Increment,
+        );
+
+        let mut work_buffer = BufferPreallocated::new(20);
+        let heuristic = StartGroupByParser {
+            parser: start_group_parenthesis,
+            start_character: b"(",
+        };
+        let stream = LayeredStreamParser::new(transport, &mut work_buffer, parse_data, heuristic);
+
+        let mut result = vec![];
+        let mut errors = vec![];
+        for x in stream {
+            match x {
+                Ok(data) => result.push(data),
+                Err(StreamParserError::Layer { layer, offset, .. }) => errors.push((layer, offset)),
+                Err(err) => panic!("Unexpected error {err}"),
+            }
+        }
+
+        assert_eq!(vec![vec![1, 2], vec![3, 4], vec![5]], result);
+        // The lower layer error is on the `x` of the transport data
+        assert_eq!(vec![(0, 22)], errors);
+    }
+}
@@ -0,0 +1,144 @@
+use std::fmt::Debug;
+
+use memmap2::Mmap;
+
+use crate::buffers::slice::BufferSlice;
+use crate::checkpoint::Checkpoint;
+use crate::heuristic::Heuristic;
+use crate::metrics::Metrics;
+use crate::parser_state::ParsableState;
+use crate::stats::{Observer, Stats};
+use crate::stream_parsers::ParserCommonFields;
+use crate::{ParserFunction, StreamParserError};
+
+/// Stream parser over a memory mapped file
+///
+/// The heuristic and the parser run directly over the mapped data, without
+/// copy nor eviction, yielding the same data as the streaming drivers.
+/// The file is mapped read-only by [BufferSlice::map].
+///
+/// The file must not be modified while it is parsed.
+pub struct MmapStreamParser<'a, O, H>
+where
+    H: Heuristic,
+    O: Debug,
+{
+    common: ParserCommonFields<'a, BufferSlice<Mmap>, O, H>,
+    /// Whether the mapped data have been accounted as read
+    mapped: bool,
+}
+
+impl<'a, O, H> MmapStreamParser<'a, O, H>
+where
+    H: Heuristic,
+    O: Debug,
+{
+    pub fn new(
+        work_buffer: &'a mut BufferSlice<Mmap>,
+        parser: ParserFunction<O>,
+        heuristic: H,
+    ) -> Self {
+        MmapStreamParser {
+            common: ParserCommonFields::new(work_buffer, parser, heuristic),
+            mapped: false,
+        }
+    }
+
+    /// Snapshot the parsing progression, the offset is the position in the file
+    pub fn checkpoint(&self) -> Checkpoint {
+        self.common.checkpoint()
+    }
+
+    /// Get the counters of the parsing activity
+    pub fn stats(&self) -> Stats {
+        self.common.stats
+    }
+
+    /// Receive the events of the parsing activity as they happen
+    pub fn with_observer(mut self, observer: impl Observer + Send + 'a) -> Self {
+        self.common.observer = Some(Box::new(observer));
+        self
+    }
+
+    /// Name the parser in the labels of its metrics, by default the name of the work buffer
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.common.metrics = Metrics::new(name);
+        self
+    }
+}
+
+impl<O, H> Iterator for MmapStreamParser<'_, O, H>
+where
+    H: Heuristic,
+    O: Debug,
+{
+    type Item = Result<O, StreamParserError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let ParsableState::NeedMoreData = self.common.state.1 {
+            // The whole file is buffered, needing more data means the end is reached
+            if self.mapped {
+                return None;
+            }
+            self.mapped = true;
+            self.common.read(self.common.work_buffer.len());
+            self.common.state.1 = ParsableState::MaybeParsable;
+        }
+
+        self.common.parse().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use utils::parsers::{parse_data, start_group_parenthesis};
+    use utils::seeder::SeederConfig;
+
+    use crate::buffers::preallocated::BufferPreallocated;
+    use crate::buffers::slice::BufferSlice;
+    use crate::stream_parsers::mmap::MmapStreamParser;
+    use crate::stream_parsers::sync_reader::StreamParser;
+    use crate::StartGroupByParser;
+
+    #[test_pretty_log::test]
+    fn test_mmap_matches_reader() {
+        let heuristic = StartGroupByParser {
+            parser: start_group_parenthesis,
+            start_character: b"(",
+        };
+        let mut rng = ChaCha8Rng::seed_from_u64(42);
+        let config = SeederConfig::new(500, 30, 2, 4, 4, 10, false);
+        let (data, expected) = config.generate(&mut rng);
+
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), &data).unwrap();
+
+        let mut work_buffer = BufferPreallocated::new(4096);
+        let mut stream = StreamParser::new(
+            data.as_slice(),
+            &mut work_buffer,
+            parse_data,
+            heuristic.clone(),
+        );
+        // Error messages embed the buffered data, only their position is compared
+        let streamed = stream.by_ref().map(Result::ok).collect::<Vec<_>>();
+        let streamed_stats = stream.stats();
+
+        let mut work_buffer = BufferSlice::map(&File::open(file.path()).unwrap()).unwrap();
+        let mut stream = MmapStreamParser::new(&mut work_buffer, parse_data, heuristic);
+        let mapped = stream.by_ref().map(Result::ok).collect::<Vec<_>>();
+
+        assert_eq!(streamed, mapped);
+        assert_eq!(expected, mapped.into_iter().flatten().collect::<Vec<_>>());
+
+        let stats = stream.stats();
+        assert_eq!(data.len() as u64, stats.bytes_read);
+        assert_eq!(streamed_stats.frames, stats.frames);
+        assert_eq!(streamed_stats.errors, stats.errors);
+    }
+}
@@ -1,13 +1,31 @@
+use std::fmt::Debug;
+use std::marker::PhantomData;
 use std::ops::{Deref, DerefMut};
 
+use crate::checkpoint::Checkpoint;
 use crate::heuristic::Heuristic;
+use crate::limits::Limits;
+use crate::logic::{parse_internal, ReturnState};
+use crate::metrics::{Metrics, Stage};
 use crate::parser_state::{ParsableState, SearchState};
-use crate::{Buffer, ParserFunction};
+use crate::stats::{Event, Observer, Stats};
+use crate::timeout::Timer;
+use crate::trace::event;
+use crate::traits::Parse;
+use crate::{Buffer, ParserFunction, StreamParserError};
 
+pub mod demux;
+pub mod layered;
+#[cfg(feature = "mmap")]
+pub mod mmap;
+#[cfg(feature = "rayon")]
+pub mod parallel;
+pub mod reassembly;
 pub mod sync_iterator;
 pub mod sync_reader;
+pub mod tee;
 
-struct ParserCommonFields<'a, B, O, H: Heuristic> {
+struct ParserCommonFields<'a, B, O, H: Heuristic, P = ParserFunction<O>> {
     /// Parsed buffer
     pub work_buffer: &'a mut B,
     /// Define both whether a new group must be searched
@@ -17,17 +35,307 @@ struct ParserCommonFields<'a, B, O, H: Heuristic> {
     /// Work buffer cursor, define at which position
     /// data in buffer are start to read
     pub cursor: usize,
+    /// Position of the first byte of the work buffer, relative to the
+    /// position of the source when the stream parser was created
+    pub offset: u64,
     /// The master used to generate parsing decision
     /// and result data yielded by stream parser
-    pub parser: ParserFunction<O>,
-    ///
+    pub parser: P,
+    /// Search the start of the next group of data
     pub heuristic: H,
+    /// Limits enforced on the parsed data
+    pub limits: Limits,
+    /// Timeouts of partial frames and idle source
+    pub timer: Timer,
+    /// Amount of data discarded by the heuristic since the last group start
+    garbage: usize,
+    /// Number of errors yielded in a row
+    errors: usize,
+    /// Counters of the parsing activity
+    pub stats: Stats,
+    /// Receive the events counted by the statistics
+    pub observer: Option<Box<dyn Observer + Send + 'a>>,
+    /// Metrics published under the name of the parser
+    pub metrics: Metrics,
     #[allow(unused)]
     /// Used to debug the system when it comes to infinite loop
     i: usize,
+    output: PhantomData<O>,
 }
 
-impl<'a, B, O, H: Heuristic> Deref for ParserCommonFields<'a, B, O, H>
+impl<'a, B, O, H: Heuristic, P: Parse<O>> ParserCommonFields<'a, B, O, H, P>
+where
+    B: Buffer,
+    O: Debug,
+{
+    fn new(work_buffer: &'a mut B, parser: P, heuristic: H) -> Self {
+        let metrics = Metrics::new(work_buffer.name());
+        ParserCommonFields {
+            work_buffer,
+            state: (SearchState::SearchForStart, ParsableState::NeedMoreData),
+            cursor: 0,
+            offset: 0,
+            parser,
+            heuristic,
+            limits: Limits::default(),
+            timer: Timer::default(),
+            garbage: 0,
+            errors: 0,
+            stats: Stats::default(),
+            observer: None,
+            metrics,
+            i: 0,
+            output: PhantomData,
+        }
+    }
+
+    /// Run the parsing logic over the work buffer, keeping track
+    /// of the data discarded by the heuristic
+    fn parse(&mut self) -> Result<Option<O>, StreamParserError> {
+        let len = self.work_buffer.len();
+
+        let mut heuristic = Measured {
+            heuristic: &mut self.heuristic,
+            discarded: 0,
+            metrics: &self.metrics,
+        };
+        let mut parser = Timed {
+            parser: &mut self.parser,
+            metrics: &self.metrics,
+        };
+        let result = parse_internal(
+            self.work_buffer,
+            &mut self.state,
+            &mut self.cursor,
+            &mut parser,
+            &mut heuristic,
+        );
+        self.metrics.iteration_end();
+        let discarded = heuristic.discarded;
+
+        // The heuristic cleans the work buffer when no group start is found
+        let remaining = self.work_buffer.len();
+        if remaining < len {
+            self.offset += (len - remaining) as u64;
+        }
+        if discarded > 0 {
+            self.garbage += discarded;
+            self.metrics.discarded(discarded);
+            self.record(Event::Discarded { size: discarded });
+        }
+        if let (Ok(Some(_)), _) | (_, SearchState::StartFound) = (&result, &self.state.0) {
+            self.garbage = 0;
+        }
+
+        match result {
+            Err(err) => Err(self.failed(err)),
+            Ok(Some(data)) => {
+                self.errors = 0;
+                self.record(Event::Frame);
+                Ok(Some(data))
+            }
+            Ok(None) => {
+                if self.cursor < self.work_buffer.len() {
+                    self.record(Event::Incomplete);
+                }
+                Ok(None)
+            }
+        }
+    }
+
+    /// Count an event and forward it to the observer
+    fn record(&mut self, event: Event) {
+        self.stats.record(&event);
+        if let Some(observer) = self.observer.as_mut() {
+            observer.observe(&event);
+        }
+    }
+
+    /// Account for the `size` bytes read from the source into the work buffer
+    fn read(&mut self, size: usize) {
+        self.record(Event::Read { size });
+        let len = self.work_buffer.len();
+        if len > self.stats.high_water_mark {
+            self.record(Event::HighWaterMark { size: len });
+        }
+    }
+
+    /// Count the errors in a row, once too many errors are yielded
+    /// the buffered data are dropped
+    fn count_error(&mut self, err: StreamParserError) -> StreamParserError {
+        self.errors += 1;
+        match self.limits.max_consecutive_errors {
+            Some(max_consecutive_errors) if self.errors >= max_consecutive_errors => {
+                event!(
+                    debug,
+                    errors = self.errors,
+                    "Too many consecutive errors, dropping buffered data"
+                );
+                self.errors = 0;
+                self.reset(false);
+                StreamParserError::TooManyErrors {
+                    max_consecutive_errors,
+                }
+            }
+            _ => err,
+        }
+    }
+
+    /// Count an error yielded by the parsing in the statistics,
+    /// the consecutive errors and the metrics
+    fn failed(&mut self, err: StreamParserError) -> StreamParserError {
+        self.record(Event::Error);
+        let err = self.count_error(err);
+        self.metrics.error(&err);
+        err
+    }
+
+    /// Check the limits before buffering more data, the
+    /// offending data are dropped when a limit is hit
+    fn check_limits(&mut self) -> Option<StreamParserError> {
+        if let Some(max_garbage) = self.limits.max_garbage {
+            if self.garbage > max_garbage {
+                event!(debug, garbage = self.garbage, "No group start found");
+                self.garbage = 0;
+                return Some(self.error(StreamParserError::GarbageExceeded { max_garbage }));
+            }
+        }
+
+        let pending = self.work_buffer.len() - self.cursor;
+        if let Some(max_frame_size) = self.limits.max_frame_size {
+            if pending > max_frame_size {
+                event!(debug, dropped = pending, "Frame too large");
+                self.reset(false);
+                return Some(self.error(StreamParserError::FrameTooLarge {
+                    max_frame_size,
+                    dropped: pending,
+                }));
+            }
+        }
+
+        None
+    }
+
+    /// Count an error yielded by the stream parser outside of the parsing
+    fn error(&self, err: StreamParserError) -> StreamParserError {
+        self.metrics.error(&err);
+        err
+    }
+
+    /// Account for the `evinced` bytes removed from the head of the work buffer
+    fn evinced(&mut self, evinced: usize) {
+        self.offset += evinced as u64;
+        self.record(Event::Eviction { size: evinced });
+    }
+
+    /// Drop the data pending in the work buffer, the parsing restarts
+    /// from scratch with the next data of the source
+    fn reset(&mut self, reset_heuristic: bool) {
+        self.offset += self.work_buffer.len() as u64;
+        self.work_buffer.reset();
+        self.cursor = 0;
+        self.state = (SearchState::SearchForStart, ParsableState::NeedMoreData);
+        if reset_heuristic {
+            self.heuristic.reset();
+        }
+    }
+
+    /// Replace the parser, data pending in the work buffer are kept
+    /// and parsed by the new parser
+    fn switch_parser(&mut self, parser: P) {
+        self.parser = parser;
+        self.state.1 = ParsableState::MaybeParsable;
+    }
+
+    /// Replace the heuristic, possibly by a heuristic of another type, the start
+    /// of the next group is searched by the new heuristic
+    fn switch_heuristic<H2: Heuristic>(self, heuristic: H2) -> ParserCommonFields<'a, B, O, H2, P> {
+        ParserCommonFields {
+            work_buffer: self.work_buffer,
+            state: (SearchState::SearchForStart, ParsableState::MaybeParsable),
+            cursor: self.cursor,
+            offset: self.offset,
+            parser: self.parser,
+            heuristic,
+            limits: self.limits,
+            timer: self.timer,
+            garbage: self.garbage,
+            errors: self.errors,
+            stats: self.stats,
+            observer: self.observer,
+            metrics: self.metrics,
+            i: self.i,
+            output: PhantomData,
+        }
+    }
+
+    fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            offset: self.offset + self.cursor as u64,
+            state: self.state.clone(),
+            heuristic: self.heuristic.state(),
+        }
+    }
+
+    /// Restore the state saved in a checkpoint, the work buffer is emptied
+    /// as data after the checkpoint offset must be provided again
+    fn restore(&mut self, checkpoint: &Checkpoint) {
+        self.work_buffer.reset();
+        self.cursor = 0;
+        self.offset = checkpoint.offset;
+        self.state = checkpoint.state.clone();
+        self.heuristic.restore(&checkpoint.heuristic);
+    }
+}
+
+/// Heuristic measuring the data discarded by the wrapped heuristic
+struct Measured<'h, H> {
+    heuristic: &'h mut H,
+    discarded: usize,
+    metrics: &'h Metrics,
+}
+
+impl<H: Heuristic> Heuristic for Measured<'_, H> {
+    fn apply<B: Buffer, R>(
+        &mut self,
+        work_buffer: &mut B,
+        state: &mut (SearchState, ParsableState),
+        cursor: &mut usize,
+    ) -> Result<Option<ReturnState<R>>, StreamParserError> {
+        let (len, start) = (work_buffer.len(), *cursor);
+        let result = self.metrics.time(Stage::Heuristic, || {
+            self.heuristic.apply(work_buffer, state, cursor)
+        });
+
+        // Either the work buffer is cleaned or the garbage before the group start is skipped
+        self.discarded += if work_buffer.len() < len {
+            len - start
+        } else {
+            cursor.saturating_sub(start)
+        };
+        result
+    }
+}
+
+/// Parser measuring the duration of the wrapped parser and the size of the frames
+struct Timed<'p, P> {
+    parser: &'p mut P,
+    metrics: &'p Metrics,
+}
+
+impl<O, P: Parse<O>> Parse<O> for Timed<'_, P> {
+    fn parse<'i>(&mut self, input: &'i [u8]) -> nom::IResult<&'i [u8], O> {
+        let result = self
+            .metrics
+            .time(Stage::Parser, || self.parser.parse(input));
+        if let Ok((remain, _)) = &result {
+            self.metrics.frame(input.len() - remain.len());
+        }
+        result
+    }
+}
+
+impl<'a, B, O, H: Heuristic, P> Deref for ParserCommonFields<'a, B, O, H, P>
 where
     B: Buffer,
 {
@@ -38,7 +346,7 @@ where
     }
 }
 
-impl<'a, B, O, H: Heuristic> DerefMut for ParserCommonFields<'a, B, O, H>
+impl<'a, B, O, H: Heuristic, P> DerefMut for ParserCommonFields<'a, B, O, H, P>
 where
     B: Buffer,
 {
@@ -0,0 +1,332 @@
+use std::collections::VecDeque;
+use std::fmt::Debug;
+#[cfg(feature = "mmap")]
+use std::fs::File;
+#[cfg(feature = "mmap")]
+use std::io;
+#[cfg(feature = "mmap")]
+use std::path::Path;
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::Arc;
+use std::vec;
+
+#[cfg(feature = "mmap")]
+use memmap2::Mmap;
+
+use crate::heuristic::Heuristic;
+use crate::parser_state::SearchState;
+use crate::stream_parsers::sync_reader::StreamParser;
+use crate::trace::event;
+use crate::{Buffer, ParserFunction, StreamParserError};
+
+/// Data parsed from a range of the input
+struct Chain<O> {
+    /// Parsed data along with the offset following them
+    items: Vec<(u64, Result<O, StreamParserError>)>,
+    /// Offset following the last parsed data, or the end of the input
+    end: u64,
+}
+
+/// Input and parsing configuration shared by the ranges parsed on the thread pool
+struct Shared<D, O, H, N> {
+    data: D,
+    /// Create the work buffer of a range
+    new_buffer: N,
+    parser: ParserFunction<O>,
+    heuristic: H,
+}
+
+/// Parse a large input on a thread pool
+///
+/// The input is split in ranges parsed in parallel, the heuristic finds the
+/// first group start after each split point. As the first data found after a
+/// split point may be a false start, the ranges are stitched together at the
+/// first offset where a range reaches the same parsing state as the previous
+/// one, ranges which never reach it are parsed again from the previous one.
+/// The data are thus the same, and in the same order, as a sequential parsing.
+///
+/// The input is owned, as a vector or a file mapped in memory by [`Self::open`],
+/// so that the ranges are parsed while the data are consumed.
+pub struct ParallelStreamParser<D, B, O, H, N>
+where
+    D: AsRef<[u8]> + Send + Sync + 'static,
+    B: Buffer,
+    H: Heuristic + Clone + Send + Sync + 'static,
+    O: Debug + Send + 'static,
+    N: Fn() -> B + Send + Sync + 'static,
+{
+    shared: Arc<Shared<D, O, H, N>>,
+    ranges: usize,
+}
+
+#[cfg(feature = "mmap")]
+impl<B, O, H, N> ParallelStreamParser<Mmap, B, O, H, N>
+where
+    B: Buffer,
+    H: Heuristic + Clone + Send + Sync + 'static,
+    O: Debug + Send + 'static,
+    N: Fn() -> B + Send + Sync + 'static,
+{
+    /// Parse a file mapped in memory, it must not be modified while it is parsed
+    pub fn open<P: AsRef<Path>>(
+        path: P,
+        new_buffer: N,
+        parser: ParserFunction<O>,
+        heuristic: H,
+    ) -> io::Result<Self> {
+        // SAFETY: the mapping is read only, the file must not be
+        // modified by other processes while mapped
+        let mmap = unsafe { Mmap::map(&File::open(path)?)? };
+        Ok(Self::new(mmap, new_buffer, parser, heuristic))
+    }
+}
+
+impl<D, B, O, H, N> ParallelStreamParser<D, B, O, H, N>
+where
+    D: AsRef<[u8]> + Send + Sync + 'static,
+    B: Buffer,
+    H: Heuristic + Clone + Send + Sync + 'static,
+    O: Debug + Send + 'static,
+    N: Fn() -> B + Send + Sync + 'static,
+{
+    pub fn new(data: D, new_buffer: N, parser: ParserFunction<O>, heuristic: H) -> Self {
+        ParallelStreamParser {
+            shared: Arc::new(Shared {
+                data,
+                new_buffer,
+                parser,
+                heuristic,
+            }),
+            ranges: rayon::current_num_threads(),
+        }
+    }
+
+    /// Define the number of ranges the input is split in,
+    /// by default the number of threads of the pool
+    pub fn with_ranges(mut self, ranges: usize) -> Self {
+        self.ranges = ranges.max(1);
+        self
+    }
+
+    /// Parse the whole input, the data are yielded in the input order as soon
+    /// as the ranges holding them are parsed
+    ///
+    /// All the ranges are queued on the thread pool, they are still parsed
+    /// if the iterator is dropped early.
+    pub fn parse(&self) -> ParallelItems<D, B, O, H, N> {
+        let length = self.shared.data.as_ref().len() as u64;
+        let ranges = self.ranges as u64;
+        let splits = (0..=ranges)
+            .map(|i| length * i / ranges)
+            .collect::<Vec<u64>>();
+
+        let pending = splits
+            .windows(2)
+            .map(|range| {
+                let (start, stop) = (range[0], range[1]);
+                let (sender, receiver) = channel();
+                let shared = self.shared.clone();
+                rayon::spawn(move || {
+                    // The iterator may have been dropped
+                    let _ = sender.send(shared.parse_range(start, stop));
+                });
+                (stop, receiver)
+            })
+            .collect();
+
+        ParallelItems {
+            shared: self.shared.clone(),
+            pending,
+            position: None,
+            items: vec![].into_iter(),
+        }
+    }
+}
+
+/// Data of a parallel parsing, in the input order
+pub struct ParallelItems<D, B, O, H, N>
+where
+    D: AsRef<[u8]> + Send + Sync + 'static,
+    B: Buffer,
+    H: Heuristic + Clone + Send + Sync + 'static,
+    O: Debug + Send + 'static,
+    N: Fn() -> B + Send + Sync + 'static,
+{
+    shared: Arc<Shared<D, O, H, N>>,
+    /// Stop offset and parsed data of the ranges not consumed yet
+    pending: VecDeque<(u64, Receiver<Chain<O>>)>,
+    /// Offset following the data consumed, none before the first range
+    position: Option<u64>,
+    /// Data of the current range
+    items: vec::IntoIter<(u64, Result<O, StreamParserError>)>,
+}
+
+impl<D, B, O, H, N> Iterator for ParallelItems<D, B, O, H, N>
+where
+    D: AsRef<[u8]> + Send + Sync + 'static,
+    B: Buffer,
+    H: Heuristic + Clone + Send + Sync + 'static,
+    O: Debug + Send + 'static,
+    N: Fn() -> B + Send + Sync + 'static,
+{
+    type Item = Result<O, StreamParserError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((_, item)) = self.items.next() {
+                return Some(item);
+            }
+
+            let (stop, receiver) = self.pending.pop_front()?;
+            let chain = receiver
+                .recv()
+                .expect("The range parsing ended without sending its data");
+
+            let (items, end) = match self.position {
+                None => (chain.items, chain.end),
+                // The range is covered by the previous ones
+                Some(position) if position >= chain.end => continue,
+                Some(position) => {
+                    let synchronized = chain.items.iter().position(|(end, _)| *end == position);
+                    match synchronized {
+                        Some(index) => {
+                            let mut items = chain.items;
+                            items.drain(..=index);
+                            (items, chain.end)
+                        }
+                        None => {
+                            event!(debug, position, "Range not synchronized, parsing it again");
+                            let chain = self.shared.parse_range(position, stop);
+                            (chain.items, chain.end)
+                        }
+                    }
+                }
+            };
+            self.items = items.into_iter();
+            self.position = Some(end);
+        }
+    }
+}
+
+impl<D, O, H, N> Shared<D, O, H, N>
+where
+    D: AsRef<[u8]>,
+    H: Heuristic + Clone,
+    O: Debug,
+{
+    /// Parse from the start offset until a data ends after the stop offset
+    fn parse_range<B>(&self, start: u64, stop: u64) -> Chain<O>
+    where
+        B: Buffer,
+        N: Fn() -> B,
+    {
+        let data = self.data.as_ref();
+        let mut work_buffer = (self.new_buffer)();
+        let mut stream = StreamParser::new(
+            &data[start as usize..],
+            &mut work_buffer,
+            self.parser,
+            self.heuristic.clone(),
+        );
+
+        let mut items = vec![];
+        while let Some(item) = stream.next() {
+            let checkpoint = stream.checkpoint();
+            let end = start + checkpoint.offset;
+            items.push((end, item));
+            if end >= stop && checkpoint.state.0 == SearchState::SearchForStart {
+                return Chain { items, end };
+            }
+        }
+
+        Chain {
+            items,
+            end: data.len() as u64,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use utils::parsers::{parse_data, start_group_parenthesis};
+    use utils::seeder::SeederConfig;
+
+    use crate::buffers::preallocated::BufferPreallocated;
+    use crate::stream_parsers::parallel::ParallelStreamParser;
+    use crate::stream_parsers::sync_reader::StreamParser;
+    use crate::StartGroupByParser;
+
+    #[test_pretty_log::test]
+    fn test_parallel_matches_sequential() {
+        let heuristic = StartGroupByParser {
+            parser: start_group_parenthesis,
+            start_character: b"(",
+        };
+
+        for seed in 0..20 {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            let config = SeederConfig::new(500, 30, 2, 4, 4, 10, false);
+            let (data, expected) = config.generate(&mut rng);
+
+            // Error messages embed the buffered data, only their position is compared
+            let mut work_buffer = BufferPreallocated::new(4096);
+            let sequential = StreamParser::new(
+                data.as_slice(),
+                &mut work_buffer,
+                parse_data,
+                heuristic.clone(),
+            )
+            .map(Result::ok)
+            .collect::<Vec<_>>();
+
+            for ranges in [1, 3, 8, 64] {
+                let parallel = ParallelStreamParser::new(
+                    data.clone(),
+                    || BufferPreallocated::new(4096),
+                    parse_data,
+                    heuristic.clone(),
+                )
+                .with_ranges(ranges)
+                .parse()
+                .map(Result::ok)
+                .collect::<Vec<_>>();
+
+                assert_eq!(sequential, parallel, "seed {seed}, {ranges} ranges");
+                let parsed = parallel.into_iter().flatten().collect::<Vec<_>>();
+                assert_eq!(expected, parsed, "seed {seed}, {ranges} ranges");
+            }
+        }
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test_pretty_log::test]
+    fn test_parallel_mapped_file() {
+        let heuristic = StartGroupByParser {
+            parser: start_group_parenthesis,
+            start_character: b"(",
+        };
+        let mut rng = ChaCha8Rng::seed_from_u64(42);
+        let config = SeederConfig::new(500, 30, 2, 4, 4, 10, false);
+        let (data, expected) = config.generate(&mut rng);
+
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), &data).unwrap();
+
+        let parsed = ParallelStreamParser::open(
+            file.path(),
+            || BufferPreallocated::new(4096),
+            parse_data,
+            heuristic,
+        )
+        .unwrap()
+        .with_ranges(8)
+        .parse()
+        .flatten()
+        .collect::<Vec<_>>();
+
+        assert_eq!(expected, parsed);
+    }
+}
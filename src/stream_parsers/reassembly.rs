@@ -0,0 +1,358 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::hash::Hash;
+
+use crate::trace::event;
+use crate::StreamParserError;
+
+/// Default number of completed messages whose identifier is remembered
+const DEFAULT_MAX_COMPLETED: usize = 256;
+
+/// A fragment of a logical message
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fragment<K, T> {
+    /// Identifier of the message the fragment belongs to
+    pub message_id: K,
+    /// Position of the fragment in the message, starting at 0
+    pub index: usize,
+    /// Whether the fragment is the last one of the message
+    pub is_last: bool,
+    pub payload: T,
+}
+
+/// Fragments received for a message not complete yet
+struct Incomplete<T> {
+    fragments: BTreeMap<usize, T>,
+    /// Index of the last fragment, once received
+    last: Option<usize>,
+    /// Size of the buffered payloads
+    size: usize,
+}
+
+impl<T: AsRef<[u8]>> Incomplete<T> {
+    fn is_complete(&self) -> bool {
+        self.last
+            .is_some_and(|last| self.fragments.len() == last + 1)
+    }
+
+    fn into_message(self) -> Vec<u8> {
+        let mut message = Vec::with_capacity(self.size);
+        for payload in self.fragments.into_values() {
+            message.extend_from_slice(payload.as_ref());
+        }
+        message
+    }
+}
+
+/// A reassembled message or an error, along with the message identifier
+pub type Reassembled<K> = (Option<K>, Result<Vec<u8>, StreamParserError>);
+
+/// Reassemble the messages split in fragments, the fragments of
+/// several messages may interleave
+///
+/// Complete messages are yielded along with their identifier. When the
+/// number of incomplete messages or the size of their buffered fragments
+/// exceed the limits, the oldest incomplete messages are expired. A late
+/// fragment of one of the last completed messages is reported as a duplicate.
+pub struct Reassembly<S, K, T>
+where
+    S: Iterator<Item = Result<Fragment<K, T>, StreamParserError>>,
+    K: Hash + Eq + Clone,
+    T: AsRef<[u8]>,
+{
+    fragments: S,
+    incomplete: HashMap<K, Incomplete<T>>,
+    /// Identifiers of the incomplete messages, oldest first
+    order: VecDeque<K>,
+    /// Size of the fragments buffered for all incomplete messages
+    size: usize,
+    max_messages: Option<usize>,
+    max_bytes: Option<usize>,
+    /// Identifiers of the last completed messages, oldest first
+    completed: VecDeque<K>,
+    max_completed: usize,
+    /// Messages and errors not yielded yet
+    pending: VecDeque<Reassembled<K>>,
+}
+
+impl<S, K, T> Reassembly<S, K, T>
+where
+    S: Iterator<Item = Result<Fragment<K, T>, StreamParserError>>,
+    K: Hash + Eq + Clone,
+    T: AsRef<[u8]>,
+{
+    pub fn new(fragments: S) -> Self {
+        Reassembly {
+            fragments,
+            incomplete: HashMap::new(),
+            order: VecDeque::new(),
+            size: 0,
+            max_messages: None,
+            max_bytes: None,
+            completed: VecDeque::new(),
+            max_completed: DEFAULT_MAX_COMPLETED,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Define the maximum number of incomplete messages
+    pub fn with_max_messages(mut self, max_messages: usize) -> Self {
+        self.max_messages = Some(max_messages);
+        self
+    }
+
+    /// Define the maximum size of the fragments buffered for all incomplete messages
+    pub fn with_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Define the number of completed messages whose identifier is remembered
+    /// to report their late fragments as duplicates, 256 by default
+    pub fn with_max_completed(mut self, max_completed: usize) -> Self {
+        self.max_completed = max_completed;
+        self
+    }
+
+    /// Get the number of incomplete messages
+    pub fn incomplete(&self) -> usize {
+        self.incomplete.len()
+    }
+
+    fn remove(&mut self, message_id: &K) -> Option<Incomplete<T>> {
+        let message = self.incomplete.remove(message_id)?;
+        self.order.retain(|id| id != message_id);
+        self.size -= message.size;
+        Some(message)
+    }
+
+    fn push(&mut self, fragment: Fragment<K, T>) {
+        let Fragment {
+            message_id,
+            index,
+            is_last,
+            payload,
+        } = fragment;
+
+        if self.completed.contains(&message_id) {
+            event!(debug, index, "Fragment of a completed message");
+            self.pending.push_back((
+                Some(message_id),
+                Err(StreamParserError::DuplicateFragment { index }),
+            ));
+            return;
+        }
+
+        if !self.incomplete.contains_key(&message_id) {
+            self.order.push_back(message_id.clone());
+            self.incomplete.insert(
+                message_id.clone(),
+                Incomplete {
+                    fragments: BTreeMap::new(),
+                    last: None,
+                    size: 0,
+                },
+            );
+        }
+        let Some(message) = self.incomplete.get_mut(&message_id) else {
+            return;
+        };
+
+        if message.fragments.contains_key(&index) {
+            self.pending.push_back((
+                Some(message_id),
+                Err(StreamParserError::DuplicateFragment { index }),
+            ));
+            return;
+        }
+
+        let conflict = match message.last {
+            Some(last) => (is_last || index > last).then_some((index, last)),
+            None if is_last => message
+                .fragments
+                .last_key_value()
+                .filter(|(max, _)| **max > index)
+                .map(|(max, _)| (*max, index)),
+            None => None,
+        };
+        if let Some((index, last)) = conflict {
+            event!(debug, index, "Malformed fragment, dropping the message");
+            self.remove(&message_id);
+            self.pending.push_back((
+                Some(message_id),
+                Err(StreamParserError::MalformedFragment { index, last }),
+            ));
+            return;
+        }
+        if is_last {
+            message.last = Some(index);
+        }
+
+        let size = payload.as_ref().len();
+        message.fragments.insert(index, payload);
+        message.size += size;
+        self.size += size;
+
+        if message.is_complete() {
+            if let Some(message) = self.remove(&message_id) {
+                self.completed.push_back(message_id.clone());
+                if self.completed.len() > self.max_completed {
+                    self.completed.pop_front();
+                }
+                self.pending
+                    .push_back((Some(message_id), Ok(message.into_message())));
+            }
+            return;
+        }
+
+        self.expire();
+    }
+
+    /// Expire the oldest incomplete messages until the limits are respected
+    fn expire(&mut self) {
+        loop {
+            let exceeded = self
+                .max_messages
+                .is_some_and(|max| self.incomplete.len() > max)
+                || self.max_bytes.is_some_and(|max| self.size > max);
+            if !exceeded {
+                return;
+            }
+            let Some(message_id) = self.order.front().cloned() else {
+                return;
+            };
+            if let Some(message) = self.remove(&message_id) {
+                event!(debug, "Expiring incomplete message");
+                self.pending.push_back((
+                    Some(message_id),
+                    Err(StreamParserError::ExpiredMessage {
+                        fragments: message.fragments.len(),
+                    }),
+                ));
+            }
+        }
+    }
+}
+
+impl<S, K, T> Iterator for Reassembly<S, K, T>
+where
+    S: Iterator<Item = Result<Fragment<K, T>, StreamParserError>>,
+    K: Hash + Eq + Clone,
+    T: AsRef<[u8]>,
+{
+    type Item = Reassembled<K>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.pending.pop_front() {
+                return Some(item);
+            }
+
+            match self.fragments.next()? {
+                Ok(fragment) => self.push(fragment),
+                Err(err) => return Some((None, Err(err))),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::stream_parsers::reassembly::{Fragment, Reassembly};
+    use crate::StreamParserError;
+
+    fn fragment(
+        message_id: u8,
+        index: usize,
+        is_last: bool,
+        payload: &'static [u8],
+    ) -> Fragment<u8, &'static [u8]> {
+        Fragment {
+            message_id,
+            index,
+            is_last,
+            payload,
+        }
+    }
+
+    #[test_pretty_log::test]
+    fn test_reassembly() {
+        let fragments = vec![
+            fragment(1, 1, false, b"cd"),
+            fragment(2, 0, false, b"xy"),
+            fragment(1, 0, false, b"ab"),
+            fragment(1, 0, false, b"ab"),
+            fragment(2, 1, true, b"z"),
+            fragment(3, 2, false, b"-"),
+            fragment(3, 1, true, b"-"),
+            fragment(1, 2, true, b"e"),
+        ];
+
+        let result = Reassembly::new(fragments.into_iter().map(Ok)).collect::<Vec<_>>();
+
+        assert!(matches!(
+            &result[..],
+            [
+                (Some(1), Err(StreamParserError::DuplicateFragment { index: 0 })),
+                (Some(2), Ok(xyz)),
+                (Some(3), Err(StreamParserError::MalformedFragment { index: 2, last: 1 })),
+                (Some(1), Ok(abcde)),
+            ] if xyz == b"xyz" && abcde == b"abcde"
+        ));
+    }
+
+    #[test_pretty_log::test]
+    fn test_reassembly_late_duplicate() {
+        let fragments = vec![
+            fragment(1, 0, false, b"ab"),
+            fragment(2, 0, true, b"x"),
+            fragment(1, 1, true, b"c"),
+            fragment(1, 1, true, b"c"),
+            fragment(2, 0, true, b"x"),
+        ];
+
+        let mut reassembly = Reassembly::new(fragments.into_iter().map(Ok)).with_max_completed(1);
+        let result = reassembly.by_ref().collect::<Vec<_>>();
+
+        // Only the last completed message is remembered
+        assert!(matches!(
+            &result[..],
+            [
+                (Some(2), Ok(x)),
+                (Some(1), Ok(abc)),
+                (Some(1), Err(StreamParserError::DuplicateFragment { index: 1 })),
+                (Some(2), Ok(_)),
+            ] if x == b"x" && abc == b"abc"
+        ));
+        assert_eq!(0, reassembly.incomplete());
+    }
+
+    #[test_pretty_log::test]
+    fn test_reassembly_expiration() {
+        let fragments = vec![
+            fragment(1, 0, false, b"ab"),
+            fragment(2, 0, false, b"cd"),
+            fragment(3, 0, false, b"ef"),
+            fragment(3, 1, false, b"ghi"),
+            fragment(2, 1, true, b"i"),
+        ];
+
+        let result = Reassembly::new(fragments.into_iter().map(Ok))
+            .with_max_messages(2)
+            .with_max_bytes(6)
+            .collect::<Vec<_>>();
+
+        assert!(matches!(
+            &result[..],
+            [
+                (
+                    Some(1),
+                    Err(StreamParserError::ExpiredMessage { fragments: 1 })
+                ),
+                (
+                    Some(2),
+                    Err(StreamParserError::ExpiredMessage { fragments: 1 })
+                ),
+            ]
+        ));
+    }
+}
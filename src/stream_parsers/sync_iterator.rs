@@ -1,13 +1,19 @@
-use std::cell::RefCell;
 use std::fmt::Debug;
+use std::time::Duration;
 
 use itertools::{unfold, Unfold};
 
+use crate::checkpoint::Checkpoint;
 use crate::heuristic::Heuristic;
-use crate::logic::parse_internal;
-use crate::parser_state::{ParsableState, SearchState};
+use crate::metrics::Metrics;
+use crate::parser_state::ParsableState;
+use crate::stats::{Observer, Stats};
 use crate::stream_parsers::ParserCommonFields;
-use crate::{debug, Buffer, ParserFunction, StreamParserError};
+use crate::timeout::Clock;
+#[cfg(feature = "trace")]
+use crate::trace::Preview;
+use crate::trace::{event, span};
+use crate::{Buffer, Limits, ParserFunction, StreamParserError};
 
 type SteamUnfold<'a, I, B, O, H> =
     Unfold<ParserState<'a, I, B, O, H>, Logic<ParserState<'a, I, B, O, H>, O>>;
@@ -31,18 +37,12 @@ where
     I: Iterator<Item = &'a [u8]>,
     H: Heuristic,
     B: Buffer,
+    O: Debug,
 {
     fn new(work_buffer: &'a mut B, iterator: I, parser: ParserFunction<O>, heuristic: H) -> Self {
         Self {
             iterator,
-            common: ParserCommonFields {
-                work_buffer,
-                state: (SearchState::SearchForStart, ParsableState::NeedMoreData),
-                cursor: 0,
-                parser,
-                heuristic,
-                i: 0,
-            },
+            common: ParserCommonFields::new(work_buffer, parser, heuristic),
         }
     }
 }
@@ -75,6 +75,84 @@ where
         let stream = unfold(logic_state, iteration_logic());
         StreamParser { stream }
     }
+
+    /// Snapshot the parsing progression, the offset is relative
+    /// to the first byte yielded by the source iterator
+    pub fn checkpoint(&self) -> Checkpoint {
+        self.stream.state.common.checkpoint()
+    }
+
+    /// Hand off the parsing to another parser, for instance once a protocol
+    /// header has been parsed, data already buffered aren't lost
+    pub fn switch_parser(&mut self, parser: ParserFunction<O>) {
+        self.stream.state.common.switch_parser(parser)
+    }
+
+    /// Hand off the search of group start to another heuristic, possibly
+    /// of another type, data already buffered aren't lost
+    pub fn switch_heuristic<H2: Heuristic>(self, heuristic: H2) -> StreamParser<'a, I, B, O, H2> {
+        let ParserState { iterator, common } = self.stream.state;
+        let logic_state = ParserState {
+            iterator,
+            common: common.switch_heuristic(heuristic),
+        };
+
+        let stream = unfold(logic_state, iteration_logic());
+        StreamParser { stream }
+    }
+
+    /// Get the counters of the parsing activity
+    pub fn stats(&self) -> Stats {
+        self.stream.state.common.stats
+    }
+
+    /// Receive the events of the parsing activity as they happen
+    pub fn with_observer(mut self, observer: impl Observer + Send + 'a) -> Self {
+        self.stream.state.common.observer = Some(Box::new(observer));
+        self
+    }
+
+    /// Define the limits enforced on untrusted input
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.stream.state.common.limits = limits;
+        self
+    }
+
+    /// Name the parser in the labels of its metrics, by default the name of the work buffer
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.stream.state.common.metrics = Metrics::new(name);
+        self
+    }
+
+    /// Drop a partial frame when the next chunk isn't received within
+    /// the timeout, a [StreamParserError::Timeout] is yielded
+    ///
+    /// The timeouts are checked when a chunk is received, an empty chunk
+    /// means that no data are available yet.
+    pub fn with_inter_byte_timeout(mut self, timeout: Duration) -> Self {
+        self.stream.state.common.timer.inter_byte = Some(timeout);
+        self
+    }
+
+    /// Drop a partial frame when it isn't complete within
+    /// the timeout, a [StreamParserError::Timeout] is yielded
+    pub fn with_frame_timeout(mut self, timeout: Duration) -> Self {
+        self.stream.state.common.timer.frame = Some(timeout);
+        self
+    }
+
+    /// Yield a [StreamParserError::Idle] when the source only yields
+    /// empty chunks during the timeout
+    pub fn with_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.stream.state.common.timer.idle = Some(timeout);
+        self
+    }
+
+    /// Define the clock measuring the timeouts, by default the system clock
+    pub fn with_clock(mut self, clock: impl Clock + Send + 'static) -> Self {
+        self.stream.state.common.timer.set_clock(clock);
+        self
+    }
 }
 
 impl<'a, I, B, O, H> Iterator for StreamParser<'a, I, B, O, H>
@@ -99,9 +177,13 @@ where
     O: Debug,
 {
     Box::new(|x: &mut ParserState<'a, I, B, O, H>| {
-        tracing::info!("New next() call");
-        tracing::debug!("At next() call state : {:?}", x.common.state);
-        tracing::trace!("Cursor: {}", x.common.cursor);
+        let _span = span!(
+            DEBUG,
+            "next",
+            offset = x.common.offset,
+            cursor = x.common.cursor,
+            state = ?x.common.state
+        );
 
         // Eviction de donnée
 
@@ -115,33 +197,64 @@ where
             // ask for or if the work_buffer is empty
             let current_len = x.common.work_buffer[x.common.cursor..].len();
             if let ((_, ParsableState::NeedMoreData), _) | (_, 0) = (&x.common.state, current_len) {
-                tracing::debug!("Asking for more data");
+                event!(debug, "Asking for more data");
+
+                if let Some(err) = x.common.check_limits() {
+                    return Some(Err(err));
+                }
 
                 let data = x.iterator.next();
                 if let Some(data) = data {
-                    tracing::trace!("New data : {}", debug!(data));
+                    event!(trace, data = %Preview(data), "New data");
+
+                    // The partial frame pending in the work buffer is dropped when it timed out
+                    let pending = x.common.work_buffer.len() - x.common.cursor;
+                    let frame_offset = x.common.offset + x.common.cursor as u64;
+                    let mut timed_out = None;
+                    if x.common.timer.expired(frame_offset, pending > 0) {
+                        let dropped = x.common.work_buffer[x.common.cursor..].to_vec();
+                        event!(debug, dropped = %Preview(&dropped), "Partial frame timed out");
+                        x.common.reset(false);
+                        timed_out = Some(dropped);
+                    } else if data.is_empty() {
+                        if let Some(idle) = x.common.timer.idle() {
+                            event!(debug, idle = ?idle, "No data");
+                            return Some(Err(StreamParserError::Idle { idle }));
+                        }
+                    }
+                    if !data.is_empty() {
+                        x.common.timer.received();
+                    }
+
                     let eviction = x.common.work_buffer.append(data, Some(x.common.cursor));
                     match eviction {
-                        Err(err) => return Some(Err(err)),
+                        Err(err) => {
+                            // The data can't be buffered, pending data are dropped
+                            // along with them to resynchronise on the next group
+                            x.common.read(data.len());
+                            x.common.reset(false);
+                            x.common.offset += data.len() as u64;
+                            return Some(Err(x.common.failed(err)));
+                        }
                         Ok(true) => {
+                            x.common.evinced(x.common.cursor);
                             x.common.cursor = 0;
                         }
                         _ => {}
                     };
+                    x.common.read(data.len());
                     // The work buffer can be parsed now
                     x.common.state.1 = ParsableState::MaybeParsable;
+
+                    if let Some(dropped) = timed_out {
+                        return Some(Err(x.common.error(StreamParserError::Timeout { dropped })));
+                    }
                 } else {
                     return None;
                 }
             }
 
-            let parse_internal_result = parse_internal(
-                x.common.work_buffer,
-                &mut x.common.state,
-                &mut x.common.cursor,
-                x.common.parser,
-                &mut x.common.heuristic,
-            );
+            let parse_internal_result = x.common.parse();
 
             match parse_internal_result {
                 Ok(Some(data)) => return Some(Ok(data)),
@@ -1,20 +1,30 @@
 use std::fmt::Debug;
-use std::io::Read;
+use std::io::{ErrorKind, Read, Seek, SeekFrom};
+use std::time::Duration;
 
 use itertools::{unfold, Unfold};
 
+use crate::checkpoint::Checkpoint;
+use crate::context::ContextParser;
 use crate::heuristic::Heuristic;
-use crate::logic::parse_internal;
+use crate::metrics::Metrics;
 use crate::parser_state::{ParsableState, SearchState};
+use crate::sources::SourceEvent;
+use crate::stats::{Observer, Stats};
 use crate::stream_parsers::ParserCommonFields;
-use crate::{Buffer, ParserFunction, StreamParserError};
+use crate::timeout::Clock;
+#[cfg(feature = "trace")]
+use crate::trace::Preview;
+use crate::trace::{event, span};
+use crate::traits::Parse;
+use crate::{Buffer, Limits, ParserFunction, StreamParserError};
 
-type SteamUnfold<'a, R, B, O, H> =
-    Unfold<ParserState<'a, R, B, O, H>, Logic<ParserState<'a, R, B, O, H>, O>>;
+type SteamUnfold<'a, R, B, O, H, P> =
+    Unfold<ParserState<'a, R, B, O, H, P>, Logic<ParserState<'a, R, B, O, H, P>, O>>;
 
 type Logic<St, O> = Box<dyn FnMut(&mut St) -> Option<Result<O, StreamParserError>>>;
 
-struct ParserState<'a, R, B, O, H>
+struct ParserState<'a, R, B, O, H, P>
 where
     R: Read,
     B: Buffer,
@@ -23,38 +33,33 @@ where
     /// Iterated data
     pub reader: R,
     /// Buffer used when data must be accumulated
-    pub common: ParserCommonFields<'a, B, O, H>,
+    pub common: ParserCommonFields<'a, B, O, H, P>,
 }
 
-impl<'a, R, B, O, H> ParserState<'a, R, B, O, H>
+impl<'a, R, B, O, H, P> ParserState<'a, R, B, O, H, P>
 where
     R: Read,
     B: Buffer,
     H: Heuristic,
+    O: Debug,
+    P: Parse<O>,
 {
-    fn new(work_buffer: &'a mut B, reader: R, parser: ParserFunction<O>, heuristic: H) -> Self {
+    fn new(work_buffer: &'a mut B, reader: R, parser: P, heuristic: H) -> Self {
         Self {
             reader,
-            common: ParserCommonFields {
-                work_buffer,
-                state: (SearchState::SearchForStart, ParsableState::NeedMoreData),
-                cursor: 0,
-                parser,
-                heuristic,
-                i: 0,
-            },
+            common: ParserCommonFields::new(work_buffer, parser, heuristic),
         }
     }
 }
 
-pub struct StreamParser<'a, R, B, O, H>
+pub struct StreamParser<'a, R, B, O, H, P = ParserFunction<O>>
 where
     R: Read,
     B: Buffer,
     H: Heuristic,
     O: Debug,
 {
-    stream: SteamUnfold<'a, R, B, O, H>,
+    stream: SteamUnfold<'a, R, B, O, H, P>,
 }
 
 impl<'a, R, B, O, H> StreamParser<'a, R, B, O, H>
@@ -70,14 +75,176 @@ where
         let stream = unfold(logic_state, iteration_logic());
         StreamParser { stream }
     }
+
+    /// Hand off the parsing to another parser, for instance once a protocol
+    /// header has been parsed, data already buffered aren't lost
+    pub fn switch_parser(&mut self, parser: ParserFunction<O>) {
+        self.stream.state.common.switch_parser(parser)
+    }
 }
 
-impl<'a, R, B, O, H> Iterator for StreamParser<'a, R, B, O, H>
+impl<'a, R, B, O, H, C, F> StreamParser<'a, R, B, O, H, ContextParser<C, F>>
 where
     R: Read,
     B: Buffer,
     H: Heuristic,
     O: Debug,
+    F: for<'i> FnMut(&mut C, &'i [u8]) -> nom::IResult<&'i [u8], O>,
+{
+    /// Create a stream parser whose parser receives a mutable context,
+    /// owned by the stream parser and kept between the parsing of each data
+    pub fn with_context(
+        reader: R,
+        work_buffer: &'a mut B,
+        context: C,
+        parser: F,
+        heuristic: H,
+    ) -> Self {
+        let parser = ContextParser::new(context, parser);
+        let logic_state = ParserState::new(work_buffer, reader, parser, heuristic);
+
+        let stream = unfold(logic_state, iteration_logic());
+        StreamParser { stream }
+    }
+
+    /// Get the context given to the parser
+    pub fn context(&self) -> &C {
+        &self.stream.state.common.parser.context
+    }
+
+    /// Get the context given to the parser, allowing to alter it between two data
+    pub fn context_mut(&mut self) -> &mut C {
+        &mut self.stream.state.common.parser.context
+    }
+
+    /// Release the reader and the context
+    pub fn into_parts(self) -> (R, C) {
+        let ParserState { reader, common, .. } = self.stream.state;
+        (reader, common.parser.context)
+    }
+}
+
+impl<'a, R, B, O, H, P> StreamParser<'a, R, B, O, H, P>
+where
+    R: Read,
+    B: Buffer,
+    H: Heuristic,
+    O: Debug,
+    P: Parse<O>,
+{
+    /// Snapshot the parsing progression, the offset is relative
+    /// to the position of the reader when the stream parser was created
+    pub fn checkpoint(&self) -> Checkpoint {
+        self.stream.state.common.checkpoint()
+    }
+
+    /// Hand off the search of group start to another heuristic, possibly
+    /// of another type, data already buffered aren't lost
+    pub fn switch_heuristic<H2: Heuristic>(
+        self,
+        heuristic: H2,
+    ) -> StreamParser<'a, R, B, O, H2, P> {
+        let ParserState { reader, common } = self.stream.state;
+        let logic_state = ParserState {
+            reader,
+            common: common.switch_heuristic(heuristic),
+        };
+
+        let stream = unfold(logic_state, iteration_logic());
+        StreamParser { stream }
+    }
+
+    /// Get the reader data are read from
+    pub fn reader(&self) -> &R {
+        &self.stream.state.reader
+    }
+
+    /// Drop a partial frame when the next data aren't read within
+    /// the timeout, a [StreamParserError::Timeout] is yielded
+    pub fn with_inter_byte_timeout(mut self, timeout: Duration) -> Self {
+        self.stream.state.common.timer.inter_byte = Some(timeout);
+        self
+    }
+
+    /// Drop a partial frame when it isn't complete within
+    /// the timeout, a [StreamParserError::Timeout] is yielded
+    pub fn with_frame_timeout(mut self, timeout: Duration) -> Self {
+        self.stream.state.common.timer.frame = Some(timeout);
+        self
+    }
+
+    /// Yield a [StreamParserError::Idle] instead of a [StreamParserError::Pending]
+    /// when the source provides no data during the timeout
+    pub fn with_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.stream.state.common.timer.idle = Some(timeout);
+        self
+    }
+
+    /// Get the counters of the parsing activity
+    pub fn stats(&self) -> Stats {
+        self.stream.state.common.stats
+    }
+
+    /// Receive the events of the parsing activity as they happen
+    pub fn with_observer(mut self, observer: impl Observer + Send + 'a) -> Self {
+        self.stream.state.common.observer = Some(Box::new(observer));
+        self
+    }
+
+    /// Define the limits enforced on untrusted input
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.stream.state.common.limits = limits;
+        self
+    }
+
+    /// Name the parser in the labels of its metrics, by default the name of the work buffer
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.stream.state.common.metrics = Metrics::new(name);
+        self
+    }
+
+    /// Define the clock measuring the timeouts, by default the system clock
+    pub fn with_clock(mut self, clock: impl Clock + Send + 'static) -> Self {
+        self.stream.state.common.timer.set_clock(clock);
+        self
+    }
+}
+
+impl<'a, R, B, O, H> StreamParser<'a, R, B, O, H>
+where
+    R: Read + Seek,
+    B: Buffer,
+    H: Heuristic,
+    O: Debug,
+{
+    /// Resume a stream parsing from a checkpoint, the reader must be at the position
+    /// the checkpointed stream parser was created at, it is moved forward to the
+    /// checkpoint offset so data already yielded aren't yielded again
+    pub fn resume(
+        mut reader: R,
+        work_buffer: &'a mut B,
+        parser: ParserFunction<O>,
+        heuristic: H,
+        checkpoint: &Checkpoint,
+    ) -> Result<Self, StreamParserError> {
+        let start = reader.stream_position()?;
+        reader.seek(SeekFrom::Start(start + checkpoint.offset))?;
+
+        let mut logic_state = ParserState::new(work_buffer, reader, parser, heuristic);
+        logic_state.common.restore(checkpoint);
+
+        let stream = unfold(logic_state, iteration_logic());
+        Ok(StreamParser { stream })
+    }
+}
+
+impl<'a, R, B, O, H, P> Iterator for StreamParser<'a, R, B, O, H, P>
+where
+    R: Read,
+    B: Buffer,
+    H: Heuristic,
+    O: Debug,
+    P: Parse<O>,
 {
     type Item = Result<O, StreamParserError>;
 
@@ -86,17 +253,22 @@ where
     }
 }
 
-fn iteration_logic<'a, R, B, O, H>() -> crate::logic::Logic<ParserState<'a, R, B, O, H>, O>
+fn iteration_logic<'a, R, B, O, H, P>() -> crate::logic::Logic<ParserState<'a, R, B, O, H, P>, O>
 where
     R: Read,
     B: Buffer,
     H: Heuristic,
     O: Debug,
+    P: Parse<O>,
 {
-    Box::new(|x: &mut ParserState<'a, R, B, O, H>| {
-        tracing::info!("New next() call");
-        tracing::debug!("At next() call state : {:?}", x.common.state);
-        tracing::trace!("Cursor: {}", x.common.cursor);
+    Box::new(|x: &mut ParserState<'a, R, B, O, H, P>| {
+        let _span = span!(
+            DEBUG,
+            "next",
+            offset = x.common.offset,
+            cursor = x.common.cursor,
+            state = ?x.common.state
+        );
 
         // Eviction de donnée
 
@@ -110,58 +282,118 @@ where
             // ask for or if the work_buffer is empty
             let full = x.common.work_buffer.len() - x.common.cursor;
             if let ((_, ParsableState::NeedMoreData), _) | (_, 0) = (&x.common.state, full) {
-                tracing::trace!("Current cursor {}", x.common.cursor);
                 if x.common.cursor != 0 {
                     x.common
                         .work_buffer
                         .evince(Some(x.common.cursor), "".as_bytes())
                         .unwrap();
+                    x.common.evinced(x.common.cursor);
+                    x.common.cursor = 0;
                 }
 
-                tracing::debug!("Asking for more data");
-                tracing::trace!(
-                    "Work buffer len {}",
-                    &x.common.work_buffer.get_write_buffer().len()
+                event!(
+                    debug,
+                    free_space = x.common.work_buffer.get_write_buffer().len(),
+                    "Asking for more data"
                 );
 
-                let size = x.reader.read(x.common.work_buffer.get_write_buffer());
+                if let Some(err) = x.common.check_limits() {
+                    return Some(Err(err));
+                }
+
+                let pending = x.common.work_buffer.len();
+                // A full work buffer can't tell the end of the source from more data,
+                // a single byte is read aside to find out
+                let mut probe = [0; 1];
+                let full = x.common.work_buffer.get_write_buffer().is_empty();
+                let size = match full {
+                    true => x.reader.read(&mut probe),
+                    false => x.reader.read(x.common.work_buffer.get_write_buffer()),
+                };
 
-                tracing::trace!("Read size {size:?}");
+                event!(trace, size = ?size, "Read from source");
 
                 match size {
-                    Err(err) => return Some(Err(err.into())),
-                    Ok(0) if x.common.work_buffer.get_write_buffer().is_empty() => {
-                        return Some(Err(StreamParserError::ExceededBufferUnknownSize {
-                            buffer_size: x.common.work_buffer.len(),
-                        }))
+                    Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                    Err(err)
+                        if err.kind() == ErrorKind::WouldBlock
+                            && x.common.timer.expired(x.common.offset, pending > 0) =>
+                    {
+                        let dropped = x.common.work_buffer.to_vec();
+                        event!(debug, dropped = %Preview(&dropped), "Partial frame timed out");
+                        x.common.reset(false);
+                        return Some(Err(x.common.error(StreamParserError::Timeout { dropped })));
                     }
+                    Err(err) => match SourceEvent::from_io_error(&err) {
+                        Some(SourceEvent::Reset { heuristic }) => {
+                            event!(debug, heuristic, "Source reset, dropping pending data");
+                            x.common.reset(heuristic);
+                            continue;
+                        }
+                        None if err.kind() == ErrorKind::WouldBlock => {
+                            if let Some(idle) = x.common.timer.idle() {
+                                event!(debug, idle = ?idle, "No data");
+                                return Some(Err(StreamParserError::Idle { idle }));
+                            }
+                            event!(debug, "No data available yet");
+                            return Some(Err(StreamParserError::Pending));
+                        }
+                        None => return Some(Err(x.common.error(err.into()))),
+                    },
                     Ok(0) => return None,
+                    Ok(size) if full => {
+                        let buffer_size = x.common.work_buffer.len();
+                        event!(
+                            debug,
+                            buffer_size,
+                            "Work buffer full, dropping buffered data"
+                        );
+                        x.common.reset(false);
+                        // The byte read aside follows the dropped data
+                        x.common.work_buffer.get_write_buffer()[..size].copy_from_slice(&probe);
+                        x.common.work_buffer.incr_cursor(size);
+                        x.common.read(size);
+                        x.common.timer.received();
+                        return Some(Err(x.common.error(
+                            StreamParserError::ExceededBufferUnknownSize { buffer_size },
+                        )));
+                    }
                     Ok(size) => {
                         x.common.state.1 = ParsableState::MaybeParsable;
                         x.common.work_buffer.incr_cursor(size);
                         x.common.cursor = 0;
+                        x.common.read(size);
+
+                        let expired = x.common.timer.expired(x.common.offset, pending > 0);
+                        x.common.timer.received();
+                        if expired {
+                            let dropped = x.common.work_buffer[..pending].to_vec();
+                            event!(debug, dropped = %Preview(&dropped), "Partial frame timed out");
+                            x.common
+                                .work_buffer
+                                .evince(Some(pending), "".as_bytes())
+                                .unwrap();
+                            x.common.evinced(pending);
+                            x.common.state.0 = SearchState::SearchForStart;
+                            return Some(Err(x
+                                .common
+                                .error(StreamParserError::Timeout { dropped })));
+                        }
                     }
                 }
             }
 
-            let parse_internal_result = parse_internal(
-                x.common.work_buffer,
-                &mut x.common.state,
-                &mut x.common.cursor,
-                x.common.parser,
-                &mut x.common.heuristic,
-            );
+            let parse_internal_result = x.common.parse();
 
             match parse_internal_result {
                 Ok(Some(data)) => return Some(Ok(data)),
                 Err(err) => {
-                    tracing::debug!("An error occured : {err}");
-                    tracing::debug!("Cursor: {} ", x.common.cursor);
+                    event!(debug, error = %err, cursor = x.common.cursor, "An error occured");
                     //x.common.work_buffer.reset();
                     return Some(Err(err));
                 }
                 _ => {
-                    tracing::debug!("Resetting work buffer");
+                    event!(trace, cursor = x.common.cursor, "No data parsed yet");
                     //x.common.work_buffer.reset()
                 }
             }
@@ -171,15 +403,77 @@ where
 
 #[cfg(test)]
 mod tests {
+    use std::io::{Cursor, ErrorKind, Read, Seek, SeekFrom};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
     use nom::AsBytes;
 
     use utils::parsers::{parse_data, start_group_parenthesis};
+    use utils::source::{FlakyReader, Source};
 
     use crate::buffers::preallocated::BufferPreallocated;
-    use crate::{StartGroupByParser, StreamParserError};
+    use crate::heuristic::Increment;
+    use crate::stats::{Event, Stats};
+    use crate::timeout::ManualClock;
+    use crate::{Limits, StartGroupByParser, StreamParserError};
 
     use super::StreamParser;
 
+    /// A live source, each read moves the clock forward then
+    /// returns the next chunk or would block
+    struct Scripted {
+        steps: Vec<(u64, Option<&'static [u8]>)>,
+        clock: Arc<ManualClock>,
+    }
+
+    impl Read for Scripted {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            if self.steps.is_empty() {
+                return Ok(0);
+            }
+            let (elapsed, chunk) = self.steps.remove(0);
+            self.clock.advance(Duration::from_secs(elapsed));
+            match chunk {
+                Some(chunk) => {
+                    buf[..chunk.len()].copy_from_slice(chunk);
+                    Ok(chunk.len())
+                }
+                None => Err(ErrorKind::WouldBlock.into()),
+            }
+        }
+    }
+
+    /// Parse the steps, with the frame, inter-byte and idle timeouts in seconds
+    fn timed_parse(
+        steps: Vec<(u64, Option<&'static [u8]>)>,
+        timeouts: (Option<u64>, Option<u64>, Option<u64>),
+    ) -> Vec<Result<Vec<u8>, StreamParserError>> {
+        let clock = Arc::new(ManualClock::default());
+        let source = Scripted {
+            steps,
+            clock: clock.clone(),
+        };
+        let mut work_buffer = BufferPreallocated::new(20);
+        let heuristic = StartGroupByParser {
+            parser: start_group_parenthesis,
+            start_character: b"(",
+        };
+        let mut stream =
+            StreamParser::new(source, &mut work_buffer, parse_data, heuristic).with_clock(clock);
+        let (frame, inter_byte, idle) = timeouts;
+        if let Some(timeout) = frame {
+            stream = stream.with_frame_timeout(Duration::from_secs(timeout));
+        }
+        if let Some(timeout) = inter_byte {
+            stream = stream.with_inter_byte_timeout(Duration::from_secs(timeout));
+        }
+        if let Some(timeout) = idle {
+            stream = stream.with_idle_timeout(Duration::from_secs(timeout));
+        }
+        stream.collect()
+    }
+
     #[test_pretty_log::test]
     fn test_parse_with_reader() {
         let data = b"noise(1,5,3,4)###(2,5)(1,88,56,42,78,5)".as_bytes();
@@ -203,4 +497,302 @@ mod tests {
             }
         }
     }
+
+    #[test_pretty_log::test]
+    fn test_parse_with_flaky_reader() {
+        let data = b"noise(1,5,3,4)###(2,5)(1,88,56,42,78,5)(x)(7)";
+        let parse = |reader: &mut dyn Read| {
+            let mut work_buffer = BufferPreallocated::new(20);
+            let heuristic = StartGroupByParser {
+                parser: start_group_parenthesis,
+                start_character: b"(",
+            };
+            StreamParser::new(reader, &mut work_buffer, parse_data, heuristic)
+                .filter(|x| !matches!(x, Err(StreamParserError::Pending)))
+                .map(Result::ok)
+                .collect::<Vec<_>>()
+        };
+
+        let expected = parse(&mut data.as_slice());
+        for seed in 0..20 {
+            let mut reader = FlakyReader::new(data.as_slice(), seed)
+                .with_short_reads(3)
+                .with_interrupted(20)
+                .with_would_block(20);
+            assert_eq!(expected, parse(&mut reader), "seed {seed}");
+        }
+    }
+
+    #[test_pretty_log::test]
+    fn test_resume_from_checkpoint() {
+        let data = b"noise(1,5,3,4)###(2,5)(1,88,56,42,78,5)abc(7)(1,a)(8,9)".as_bytes();
+        let heuristic = || StartGroupByParser {
+            parser: start_group_parenthesis,
+            start_character: b"(",
+        };
+
+        let mut work_buffer = BufferPreallocated::new(20);
+        let expected = StreamParser::new(data, &mut work_buffer, parse_data, heuristic())
+            .flatten()
+            .collect::<Vec<Vec<u8>>>();
+
+        let mut work_buffer = BufferPreallocated::new(20);
+        let mut stream =
+            StreamParser::new(Cursor::new(data), &mut work_buffer, parse_data, heuristic());
+        let mut result = stream.by_ref().take(3).flatten().collect::<Vec<Vec<u8>>>();
+        let checkpoint = stream.checkpoint();
+        drop(stream);
+
+        let mut work_buffer = BufferPreallocated::new(20);
+        let stream = StreamParser::resume(
+            Cursor::new(data),
+            &mut work_buffer,
+            parse_data,
+            heuristic(),
+            &checkpoint,
+        )
+        .unwrap();
+        result.extend(stream.flatten());
+
+        assert_eq!(expected, result);
+    }
+
+    #[test_pretty_log::test]
+    fn test_resume_from_checkpoint_at_reader_position() {
+        let data = b"header(1,5,3,4)###(2,5)(1,88,56,42,78,5)abc(7)(8,9)".as_bytes();
+        let heuristic = || StartGroupByParser {
+            parser: start_group_parenthesis,
+            start_character: b"(",
+        };
+        let reader = || {
+            let mut reader = Cursor::new(data);
+            reader.seek(SeekFrom::Start(6)).unwrap();
+            reader
+        };
+
+        let mut work_buffer = BufferPreallocated::new(20);
+        let mut stream = StreamParser::new(reader(), &mut work_buffer, parse_data, heuristic());
+        let mut result = stream.by_ref().take(2).flatten().collect::<Vec<Vec<u8>>>();
+        let checkpoint = stream.checkpoint();
+        drop(stream);
+
+        // The offset doesn't count the data before the reader position
+        assert_eq!(17, checkpoint.offset);
+
+        let mut work_buffer = BufferPreallocated::new(20);
+        let mut stream = StreamParser::resume(
+            reader(),
+            &mut work_buffer,
+            parse_data,
+            heuristic(),
+            &checkpoint,
+        )
+        .unwrap();
+        result.extend(stream.by_ref().flatten());
+
+        assert_eq!(
+            vec![
+                vec![1, 5, 3, 4],
+                vec![2, 5],
+                vec![1, 88, 56, 42, 78, 5],
+                vec![7],
+                vec![8, 9]
+            ],
+            result
+        );
+        assert_eq!(data.len() as u64 - 6, stream.checkpoint().offset);
+    }
+
+    #[test_pretty_log::test]
+    fn test_parse_with_context() {
+        use nom::bytes::streaming::tag;
+        use nom::sequence::delimited;
+        use nom::IResult;
+
+        #[derive(Default)]
+        struct Context {
+            scale: Option<u8>,
+            sequence: usize,
+        }
+
+        fn parser<'i>(context: &mut Context, input: &'i [u8]) -> IResult<&'i [u8], Vec<u8>> {
+            let Some(scale) = context.scale else {
+                let (remain, scale) =
+                    delimited(tag("S"), nom::character::complete::u8, tag(";"))(input)?;
+                context.scale = Some(scale);
+                return Ok((remain, vec![]));
+            };
+            let (remain, data) = parse_data(input)?;
+            context.sequence += 1;
+            Ok((remain, data.iter().map(|x| x * scale).collect()))
+        }
+
+        let data = b"S2;(1,2)(3)(4,5)".as_bytes();
+        let mut work_buffer = BufferPreallocated::new(20);
+        let mut stream = StreamParser::with_context(
+            data,
+            &mut work_buffer,
+            Context::default(),
+            parser,
+            Increment,
+        );
+
+        assert_eq!(Some(vec![]), stream.next().and_then(|x| x.ok()));
+        assert_eq!(Some(vec![2, 4]), stream.next().and_then(|x| x.ok()));
+        stream.context_mut().scale = Some(10);
+        assert_eq!(Some(vec![30]), stream.next().and_then(|x| x.ok()));
+        assert_eq!(Some(vec![40, 50]), stream.next().and_then(|x| x.ok()));
+        assert!(stream.next().is_none());
+
+        let (_, context) = stream.into_parts();
+        assert_eq!(3, context.sequence);
+    }
+
+    #[test_pretty_log::test]
+    fn test_frame_and_idle_timeouts() {
+        let steps = vec![
+            (1, Some(b"(1,2".as_slice())),
+            (1, None),
+            (1, None),
+            (1, None),
+            (1, Some(b"(3)".as_slice())),
+            (1, None),
+            (1, None),
+            (1, None),
+            (1, None),
+        ];
+        let result = timed_parse(steps, (Some(3), None, Some(3)));
+
+        assert!(matches!(
+            &result[..],
+            [
+                Err(StreamParserError::Pending),
+                Err(StreamParserError::Pending),
+                Err(StreamParserError::Timeout { dropped }),
+                Ok(data),
+                Err(StreamParserError::Pending),
+                Err(StreamParserError::Pending),
+                Err(StreamParserError::Idle { .. }),
+                Err(StreamParserError::Pending),
+            ] if dropped == b"(1,2" && data == &vec![3]
+        ));
+    }
+
+    #[test_pretty_log::test]
+    fn test_inter_byte_timeout() {
+        let steps = vec![(1, Some(b"(1,".as_slice())), (5, Some(b"2)(4)".as_slice()))];
+        let result = timed_parse(steps, (None, Some(2), None));
+
+        assert!(matches!(
+            &result[..],
+            [Err(StreamParserError::Timeout { dropped }), Ok(data)]
+                if dropped == b"(1," && data == &vec![4]
+        ));
+    }
+
+    /// Parse data read by chunks of 4 bytes
+    fn limited_parse(data: &[u8], limits: Limits) -> Vec<Result<Vec<u8>, StreamParserError>> {
+        let source = Source::new(data);
+        let mut work_buffer = BufferPreallocated::new(20);
+        let heuristic = StartGroupByParser {
+            parser: start_group_parenthesis,
+            start_character: b"(",
+        };
+        StreamParser::new(source, &mut work_buffer, parse_data, heuristic)
+            .with_limits(limits)
+            .collect()
+    }
+
+    #[test_pretty_log::test]
+    fn test_max_frame_size() {
+        let result = limited_parse(
+            b"(1,2,3,4,5,6,7,8,9)(5)",
+            Limits::default().with_max_frame_size(8),
+        );
+
+        assert!(matches!(
+            &result[..],
+            [
+                Err(StreamParserError::FrameTooLarge {
+                    max_frame_size: 8,
+                    dropped: 12
+                }),
+                Ok(data),
+            ] if data == &vec![5]
+        ));
+    }
+
+    #[test_pretty_log::test]
+    fn test_max_garbage() {
+        let result = limited_parse(
+            b"(1)abcdefghijklmnop(2)",
+            Limits::default().with_max_garbage(8),
+        );
+
+        assert!(matches!(
+            &result[..],
+            [
+                Ok(first),
+                Err(StreamParserError::GarbageExceeded { max_garbage: 8 }),
+                Ok(second),
+            ] if first == &vec![1] && second == &vec![2]
+        ));
+    }
+
+    #[test_pretty_log::test]
+    fn test_max_consecutive_errors() {
+        let result = limited_parse(
+            b"(a)(b)(c)(d)##(1)",
+            Limits::default().with_max_consecutive_errors(3),
+        );
+
+        assert!(matches!(
+            &result[..],
+            [
+                Err(StreamParserError::Nom(_)),
+                Err(StreamParserError::Nom(_)),
+                Err(StreamParserError::TooManyErrors {
+                    max_consecutive_errors: 3
+                }),
+                ..,
+                Ok(data),
+            ] if data == &vec![1]
+        ));
+    }
+
+    #[test_pretty_log::test]
+    fn test_stats() {
+        let data = b"##(1,2)ab(3)(x)";
+        let source = Source::new(data);
+        let mut work_buffer = BufferPreallocated::new(20);
+        let heuristic = StartGroupByParser {
+            parser: start_group_parenthesis,
+            start_character: b"(",
+        };
+
+        let events = Arc::new(Mutex::new(vec![]));
+        let observed = events.clone();
+        let mut stream = StreamParser::new(source, &mut work_buffer, parse_data, heuristic)
+            .with_observer(move |event: &Event| observed.lock().unwrap().push(event.clone()));
+        stream.by_ref().for_each(drop);
+
+        assert_eq!(
+            Stats {
+                bytes_read: 15,
+                frames: 2,
+                errors: 1,
+                discarded: 6,
+                evictions: 2,
+                high_water_mark: 6,
+                incomplete: 1,
+            },
+            stream.stats()
+        );
+
+        let mut replayed = Stats::default();
+        for event in events.lock().unwrap().iter() {
+            replayed.record(event);
+        }
+        assert_eq!(stream.stats(), replayed);
+    }
 }
@@ -0,0 +1,286 @@
+use std::collections::VecDeque;
+use std::fmt::Debug;
+use std::io::{ErrorKind, Read};
+
+use crate::heuristic::Heuristic;
+use crate::parser_state::ParsableState;
+use crate::sources::SourceEvent;
+use crate::stream_parsers::ParserCommonFields;
+use crate::trace::event;
+use crate::{Buffer, ParserFunction, StreamParserError};
+
+/// A parser, its heuristic and its work buffer, fed with the chunks read by a [Tee]
+trait Branch<T> {
+    /// Parse a chunk, pushing the tagged results along with their end offset
+    fn feed(&mut self, chunk: &[u8], parsed: &mut Vec<(u64, T)>);
+    /// Drop the data pending in the work buffer
+    fn reset(&mut self, reset_heuristic: bool);
+}
+
+struct ParserBranch<'a, B, O, H: Heuristic, T> {
+    common: ParserCommonFields<'a, B, O, H>,
+    /// Wrap the results of the branch in the type yielded by the [Tee]
+    tag: fn(Result<O, StreamParserError>) -> T,
+}
+
+impl<'a, B, O, H, T> Branch<T> for ParserBranch<'a, B, O, H, T>
+where
+    B: Buffer,
+    H: Heuristic,
+    O: Debug,
+{
+    fn feed(&mut self, mut chunk: &[u8], parsed: &mut Vec<(u64, T)>) {
+        while !chunk.is_empty() {
+            let common = &mut self.common;
+            if common.cursor != 0 {
+                common
+                    .work_buffer
+                    .evince(Some(common.cursor), "".as_bytes())
+                    .unwrap();
+                common.evinced(common.cursor);
+                common.cursor = 0;
+            }
+
+            let write_buffer = common.work_buffer.get_write_buffer();
+            if write_buffer.is_empty() {
+                let buffer_size = common.work_buffer.len();
+                event!(
+                    debug,
+                    buffer_size,
+                    "Branch work buffer full, dropping pending data"
+                );
+                common.reset(false);
+                let err = StreamParserError::ExceededBufferUnknownSize { buffer_size };
+                parsed.push((common.offset, (self.tag)(Err(common.error(err)))));
+                continue;
+            }
+
+            let size = write_buffer.len().min(chunk.len());
+            write_buffer[..size].copy_from_slice(&chunk[..size]);
+            common.work_buffer.incr_cursor(size);
+            common.read(size);
+            common.state.1 = ParsableState::MaybeParsable;
+            chunk = &chunk[size..];
+
+            while let ParsableState::MaybeParsable = common.state.1 {
+                let result = match common.parse() {
+                    Ok(Some(data)) => Ok(data),
+                    Ok(None) => continue,
+                    Err(err) => Err(err),
+                };
+                let end = common.offset + common.cursor as u64;
+                parsed.push((end, (self.tag)(result)));
+            }
+        }
+    }
+
+    fn reset(&mut self, reset_heuristic: bool) {
+        self.common.reset(reset_heuristic)
+    }
+}
+
+/// Stream parser reading each chunk of a source once and parsing it with
+/// several branches, each one made of a parser, a heuristic and a work buffer
+///
+/// Branches search their group start and resynchronise independently. Their
+/// results are wrapped by a tag function, typically a variant of an enum, so
+/// the data of all branches are yielded by a single iterator. Errors of the
+/// source are yielded as is.
+///
+/// The results of each chunk are yielded in the order of their end in the source,
+/// results ending at the same position are yielded in the order of the branches.
+/// A result whose parser needs the next chunk to complete it is yielded with
+/// the results of the next chunk.
+pub struct Tee<'a, R: Read, T> {
+    reader: R,
+    chunk: Vec<u8>,
+    branches: Vec<Box<dyn Branch<T> + 'a>>,
+    /// Results of the branches for the last chunk, along with their end offset
+    chunk_parsed: Vec<(u64, T)>,
+    /// Results parsed but not yielded yet
+    parsed: VecDeque<T>,
+}
+
+impl<'a, R: Read, T> Tee<'a, R, T> {
+    pub fn new(reader: R) -> Self {
+        Tee {
+            reader,
+            chunk: vec![0; 4096],
+            branches: vec![],
+            chunk_parsed: vec![],
+            parsed: VecDeque::new(),
+        }
+    }
+
+    /// Define the size of the chunks read from the source
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk = vec![0; chunk_size.max(1)];
+        self
+    }
+
+    /// Add a branch, its results are wrapped by the tag function
+    pub fn with_branch<B, O, H>(
+        mut self,
+        work_buffer: &'a mut B,
+        parser: ParserFunction<O>,
+        heuristic: H,
+        tag: fn(Result<O, StreamParserError>) -> T,
+    ) -> Self
+    where
+        B: Buffer,
+        H: Heuristic + 'a,
+        O: Debug + 'a,
+        T: 'a,
+    {
+        self.branches.push(Box::new(ParserBranch {
+            common: ParserCommonFields::new(work_buffer, parser, heuristic),
+            tag,
+        }));
+        self
+    }
+}
+
+impl<'a, R: Read, T> Iterator for Tee<'a, R, T> {
+    type Item = Result<T, StreamParserError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.parsed.pop_front() {
+                return Some(Ok(item));
+            }
+
+            let size = match self.reader.read(&mut self.chunk) {
+                Ok(0) => return None,
+                Ok(size) => size,
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                Err(err) => match SourceEvent::from_io_error(&err) {
+                    Some(SourceEvent::Reset { heuristic }) => {
+                        event!(debug, heuristic, "Source reset, dropping pending data");
+                        for branch in &mut self.branches {
+                            branch.reset(heuristic);
+                        }
+                        continue;
+                    }
+                    None if err.kind() == ErrorKind::WouldBlock => {
+                        return Some(Err(StreamParserError::Pending))
+                    }
+                    None => return Some(Err(err.into())),
+                },
+            };
+
+            for branch in &mut self.branches {
+                branch.feed(&self.chunk[..size], &mut self.chunk_parsed);
+            }
+            // The sort is stable, the order of the branches is kept
+            self.chunk_parsed.sort_by_key(|(end, _)| *end);
+            self.parsed
+                .extend(self.chunk_parsed.drain(..).map(|(_, item)| item));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nom::bytes::streaming::{tag, take_until};
+    use nom::sequence::delimited;
+    use nom::IResult;
+
+    use utils::parsers::{parse_data, start_group_parenthesis};
+
+    use crate::buffers::preallocated::BufferPreallocated;
+    use crate::stream_parsers::tee::Tee;
+    use crate::{StartGroupByParser, StreamParserError};
+
+    #[derive(Debug)]
+    enum Extracted {
+        Telemetry(Result<Vec<u8>, StreamParserError>),
+        Debug(Result<String, StreamParserError>),
+    }
+
+    fn parse_line(input: &[u8]) -> IResult<&[u8], String> {
+        let (remain, line) = delimited(tag("$"), take_until("\n"), tag("\n"))(input)?;
+        Ok((remain, String::from_utf8_lossy(line).to_string()))
+    }
+
+    fn start_line(input: &[u8]) -> IResult<&[u8], &[u8]> {
+        take_until("$")(input)
+    }
+
+    #[test_pretty_log::test]
+    fn test_tee() {
+        let data = b"(1,2)$boot ok\n#(3)$x(9)\n(4,5)";
+        let mut telemetry_buffer = BufferPreallocated::new(10);
+        let mut debug_buffer = BufferPreallocated::new(10);
+
+        let tee = Tee::new(data.as_slice())
+            .with_chunk_size(4)
+            .with_branch(
+                &mut telemetry_buffer,
+                parse_data,
+                StartGroupByParser {
+                    parser: start_group_parenthesis,
+                    start_character: b"(",
+                },
+                Extracted::Telemetry,
+            )
+            .with_branch(
+                &mut debug_buffer,
+                parse_line,
+                StartGroupByParser {
+                    parser: start_line,
+                    start_character: b"$",
+                },
+                Extracted::Debug,
+            );
+
+        let mut telemetry = vec![];
+        let mut debug = vec![];
+        for x in tee {
+            match x.unwrap() {
+                Extracted::Telemetry(Ok(data)) => telemetry.push(data),
+                Extracted::Debug(Ok(line)) => debug.push(line),
+                _ => {}
+            }
+        }
+
+        assert_eq!(vec![vec![1, 2], vec![3], vec![9], vec![4, 5]], telemetry);
+        assert_eq!(vec!["boot ok".to_string(), "x(9)".to_string()], debug);
+    }
+
+    #[test_pretty_log::test]
+    fn test_tee_ordering() {
+        let data = b"$a\n(1)$b\n(2)(3)$c\n";
+        let mut telemetry_buffer = BufferPreallocated::new(20);
+        let mut debug_buffer = BufferPreallocated::new(20);
+
+        let tee = Tee::new(data.as_slice())
+            .with_branch(
+                &mut telemetry_buffer,
+                parse_data,
+                StartGroupByParser {
+                    parser: start_group_parenthesis,
+                    start_character: b"(",
+                },
+                Extracted::Telemetry,
+            )
+            .with_branch(
+                &mut debug_buffer,
+                parse_line,
+                StartGroupByParser {
+                    parser: start_line,
+                    start_character: b"$",
+                },
+                Extracted::Debug,
+            );
+
+        // The results of both branches are interleaved as in the source
+        let result = tee
+            .map(|x| match x.unwrap() {
+                Extracted::Telemetry(data) => format!("{:?}", data.unwrap()),
+                Extracted::Debug(line) => line.unwrap(),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(vec!["a", "[1]", "b", "[2]", "[3]", "c"], result);
+    }
+}
@@ -0,0 +1,140 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Source of time used by the timeouts of the stream parsers
+pub trait Clock {
+    fn now(&self) -> Instant;
+}
+
+/// The system monotonic clock
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A clock only moving forward when asked to, mostly for testing purpose
+pub struct ManualClock {
+    now: Mutex<Instant>,
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        ManualClock {
+            now: Mutex::new(Instant::now()),
+        }
+    }
+}
+
+impl ManualClock {
+    /// Move the clock forward
+    pub fn advance(&self, duration: Duration) {
+        *self.now.lock().unwrap() += duration;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        *self.now.lock().unwrap()
+    }
+}
+
+impl<C: Clock + ?Sized> Clock for Arc<C> {
+    fn now(&self) -> Instant {
+        (**self).now()
+    }
+}
+
+/// Keep track of the time elapsed since data were received
+/// and since the pending partial frame started
+pub(crate) struct Timer {
+    clock: Box<dyn Clock + Send>,
+    /// Maximum delay between two reads of a partial frame
+    pub inter_byte: Option<Duration>,
+    /// Maximum delay to complete a partial frame
+    pub frame: Option<Duration>,
+    /// Maximum delay without data before notifying the caller
+    pub idle: Option<Duration>,
+    /// Time of the last read data
+    last_data: Option<Instant>,
+    /// Offset and start time of the pending partial frame
+    frame_start: Option<(u64, Instant)>,
+    /// Start of the current idle period
+    idle_since: Option<Instant>,
+}
+
+impl Default for Timer {
+    fn default() -> Self {
+        Timer {
+            clock: Box::new(SystemClock),
+            inter_byte: None,
+            frame: None,
+            idle: None,
+            last_data: None,
+            frame_start: None,
+            idle_since: None,
+        }
+    }
+}
+
+impl Timer {
+    pub fn set_clock(&mut self, clock: impl Clock + Send + 'static) {
+        self.clock = Box::new(clock);
+        self.last_data = None;
+        self.frame_start = None;
+        self.idle_since = None;
+    }
+
+    /// Whether the partial frame starting at `offset`, if any, timed out
+    pub fn expired(&mut self, offset: u64, pending: bool) -> bool {
+        if self.inter_byte.is_none() && self.frame.is_none() {
+            return false;
+        }
+        if !pending {
+            self.frame_start = None;
+            return false;
+        }
+
+        let now = self.clock.now();
+        let start = match self.frame_start {
+            Some((start_offset, start)) if start_offset == offset => start,
+            _ => {
+                let start = self.last_data.unwrap_or(now);
+                self.frame_start = Some((offset, start));
+                start
+            }
+        };
+
+        let frame = self
+            .frame
+            .is_some_and(|timeout| now.saturating_duration_since(start) >= timeout);
+        let inter_byte = self.inter_byte.is_some_and(|timeout| {
+            self.last_data
+                .is_some_and(|last| now.saturating_duration_since(last) >= timeout)
+        });
+        frame || inter_byte
+    }
+
+    /// Record the reception of data
+    pub fn received(&mut self) {
+        if self.inter_byte.is_some() || self.frame.is_some() || self.idle.is_some() {
+            let now = self.clock.now();
+            self.last_data = Some(now);
+            self.idle_since = Some(now);
+        }
+    }
+
+    /// Get the idle timeout if it elapsed without data, a new idle period starts
+    pub fn idle(&mut self) -> Option<Duration> {
+        let timeout = self.idle?;
+        let now = self.clock.now();
+        let since = *self.idle_since.get_or_insert(now);
+        if now.saturating_duration_since(since) < timeout {
+            return None;
+        }
+        self.idle_since = Some(now);
+        Some(timeout)
+    }
+}
@@ -0,0 +1,67 @@
+//! Trace points of the crate, compiled out without the `trace` feature
+
+use std::fmt::{Debug, Display, Formatter};
+
+/// Maximum number of bytes displayed by a [Preview]
+const PREVIEW_LEN: usize = 32;
+
+/// Lazy hexadecimal preview of the first bytes of a slice, only formatted
+/// when the trace point is enabled or the error is displayed
+pub(crate) struct Preview<'a>(pub &'a [u8]);
+
+impl Display for Preview<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let shown = &self.0[..self.0.len().min(PREVIEW_LEN)];
+        for byte in shown {
+            write!(f, "{byte:02x}")?;
+        }
+        if self.0.len() > shown.len() {
+            write!(f, "..(+{})", self.0.len() - shown.len())?;
+        }
+        Ok(())
+    }
+}
+
+impl Debug for Preview<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(self, f)
+    }
+}
+
+/// Emit an event at the given level, `event!(debug, field = value, "message")`
+macro_rules! event {
+    ($level:ident, $($arg:tt)+) => {
+        #[cfg(feature = "trace")]
+        tracing::$level!($($arg)+);
+    };
+}
+
+/// Enter a span until the returned guard is dropped, `span!(DEBUG, "name", field = value)`
+macro_rules! span {
+    ($level:ident, $($arg:tt)+) => {{
+        #[cfg(feature = "trace")]
+        let guard = tracing::span!(tracing::Level::$level, $($arg)+).entered();
+        #[cfg(not(feature = "trace"))]
+        let guard = $crate::trace::NoSpan;
+        guard
+    }};
+}
+
+/// Guard returned by [span] when trace points are compiled out
+#[cfg(not(feature = "trace"))]
+pub(crate) struct NoSpan;
+
+pub(crate) use event;
+pub(crate) use span;
+
+#[cfg(test)]
+mod tests {
+    use crate::trace::Preview;
+
+    #[test]
+    fn preview_is_bounded() {
+        assert_eq!("28312c", Preview(b"(1,").to_string());
+        let preview = Preview(&[0xab; 40]).to_string();
+        assert_eq!(format!("{}..(+8)", "ab".repeat(32)), preview);
+    }
+}
@@ -1,4 +1,4 @@
-use std::ops::{Deref, DerefMut};
+use std::ops::Deref;
 
 use crate::errors::StreamParserError;
 
@@ -7,8 +7,19 @@ pub type ParserFunction<R> = fn(&[u8]) -> nom::IResult<&[u8], R>;
 /// Define a parser which found the start of a group of data
 pub type ParserFunctionStartGroup = fn(&[u8]) -> nom::IResult<&[u8], &[u8]>;
 
+/// Define the parser generating the data of the final stream
+pub trait Parse<R> {
+    fn parse<'i>(&mut self, input: &'i [u8]) -> nom::IResult<&'i [u8], R>;
+}
+
+impl<R> Parse<R> for ParserFunction<R> {
+    fn parse<'i>(&mut self, input: &'i [u8]) -> nom::IResult<&'i [u8], R> {
+        self(input)
+    }
+}
+
 /// Define the behavior expected by a buffer used while parsing data
-pub trait Buffer: Deref<Target = [u8]> + DerefMut {
+pub trait Buffer: Deref<Target = [u8]> {
     /// Add data to buffer, if evincealble declares an amount of data removable
     fn append(
         &mut self,
@@ -25,4 +36,8 @@ pub trait Buffer: Deref<Target = [u8]> + DerefMut {
     fn get_write_buffer(&mut self) -> &mut [u8];
     fn reset(&mut self);
     fn evince(&mut self, evinceable: Option<usize>, other: &[u8]) -> Result<(), StreamParserError>;
+    /// Name of the buffer, labelling the metrics of the parser using it
+    fn name(&self) -> &str {
+        ""
+    }
 }
@@ -0,0 +1,242 @@
+//! Check the stream parsers against a plain nom loop over the whole input,
+//! shared by the integration tests and the fuzz targets
+
+use std::fmt::{Debug, Display, Formatter};
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use nom_stream_parser::heuristic::Heuristic;
+use nom_stream_parser::stream_parsers::{sync_iterator, sync_reader};
+use nom_stream_parser::{Buffer, ParserFunction, StartGroupByParser};
+use utils::debug;
+use utils::source::Chunked;
+
+/// Stream parser checked against the reference parse
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Driver {
+    Iterator,
+    Reader,
+}
+
+/// A chunking of the input for which a stream parser doesn't
+/// yield the same items as the reference whole input parse
+pub struct Mismatch<'i, O> {
+    pub driver: Driver,
+    pub chunks: Vec<&'i [u8]>,
+    pub expected: Vec<Option<O>>,
+    pub found: Vec<Option<O>>,
+}
+
+impl<O: Debug> Display for Mismatch<'_, O> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let chunks: Vec<_> = self.chunks.iter().map(|chunk| debug!(chunk)).collect();
+        writeln!(f, "{:?} stream parser mismatch", self.driver)?;
+        writeln!(f, "minimal chunking : {chunks:?}")?;
+        writeln!(f, "expected : {:?}", self.expected)?;
+        write!(f, "found    : {:?}", self.found)
+    }
+}
+
+impl<O: Debug> Debug for Mismatch<'_, O> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(self, f)
+    }
+}
+
+/// Check that the stream parsers yield the same items whatever the chunking
+/// of the input, compared to the [reference] parse of the whole input
+///
+/// The input is split at every single split point, in chunks of every size up
+/// to the maximum chunk size, and in seeded random chunks. Items are compared
+/// without the error details, errors only have to be yielded at the same place.
+pub struct Equivalence<O, H, G, F> {
+    parser: ParserFunction<O>,
+    heuristic: H,
+    group_start: G,
+    new_buffer: F,
+    max_chunk_size: usize,
+    random_chunkings: u64,
+}
+
+impl<O, H, G, F, B> Equivalence<O, H, G, F>
+where
+    O: Debug + PartialEq,
+    H: Heuristic + Clone,
+    G: Fn(&[u8]) -> Option<usize>,
+    F: Fn() -> B,
+    B: Buffer,
+{
+    /// The group start search of the reference parse mirrors the heuristic, the
+    /// buffer factory provides a new work buffer to each parse, large enough
+    /// to hold the whole input
+    pub fn new(parser: ParserFunction<O>, heuristic: H, group_start: G, new_buffer: F) -> Self {
+        Equivalence {
+            parser,
+            heuristic,
+            group_start,
+            new_buffer,
+            max_chunk_size: 16,
+            random_chunkings: 64,
+        }
+    }
+
+    pub fn with_max_chunk_size(mut self, max_chunk_size: usize) -> Self {
+        self.max_chunk_size = max_chunk_size.max(1);
+        self
+    }
+
+    pub fn with_random_chunkings(mut self, random_chunkings: u64) -> Self {
+        self.random_chunkings = random_chunkings;
+        self
+    }
+
+    /// Panic with the minimal failing chunking on mismatch
+    pub fn check(&self, input: &[u8]) {
+        if let Err(mismatch) = self.verify(input) {
+            panic!("{mismatch}")
+        }
+    }
+
+    /// Get the minimal failing chunking on mismatch
+    pub fn verify<'i>(&self, input: &'i [u8]) -> Result<(), Mismatch<'i, O>> {
+        let expected = reference(self.parser, &self.group_start, input);
+
+        for driver in [Driver::Iterator, Driver::Reader] {
+            for splits in self.chunkings(input.len()) {
+                if self.parse(driver, input, &splits) != expected {
+                    return Err(self.minimize(driver, input, splits, expected));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Every chunking to check, as the offsets where the input is split
+    fn chunkings(&self, len: usize) -> impl Iterator<Item = Vec<usize>> + '_ {
+        let single = (1..len).map(|split| vec![split]);
+        let fixed = (1..=self.max_chunk_size).map(move |size| (size..len).step_by(size).collect());
+        let random = (0..self.random_chunkings).map(move |seed| {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            let mut splits = vec![];
+            let mut split = rng.gen_range(1..=self.max_chunk_size);
+            while split < len {
+                splits.push(split);
+                split += rng.gen_range(1..=self.max_chunk_size);
+            }
+            splits
+        });
+        single.chain(fixed).chain(random)
+    }
+
+    /// Remove the split offsets not needed to reproduce the mismatch
+    fn minimize<'i>(
+        &self,
+        driver: Driver,
+        input: &'i [u8],
+        mut splits: Vec<usize>,
+        expected: Vec<Option<O>>,
+    ) -> Mismatch<'i, O> {
+        let mut i = 0;
+        while i < splits.len() {
+            let mut candidate = splits.clone();
+            candidate.remove(i);
+            if self.parse(driver, input, &candidate) != expected {
+                splits = candidate;
+            } else {
+                i += 1;
+            }
+        }
+
+        Mismatch {
+            driver,
+            found: self.parse(driver, input, &splits),
+            chunks: chunks(input, &splits),
+            expected,
+        }
+    }
+
+    fn parse(&self, driver: Driver, input: &[u8], splits: &[usize]) -> Vec<Option<O>> {
+        let mut work_buffer = (self.new_buffer)();
+        let heuristic = self.heuristic.clone();
+        match driver {
+            Driver::Iterator => sync_iterator::StreamParser::new(
+                chunks(input, splits).into_iter(),
+                &mut work_buffer,
+                self.parser,
+                heuristic,
+            )
+            .map(Result::ok)
+            .collect(),
+            Driver::Reader => sync_reader::StreamParser::new(
+                Chunked::new(chunks(input, splits)),
+                &mut work_buffer,
+                self.parser,
+                heuristic,
+            )
+            .map(Result::ok)
+            .collect(),
+        }
+    }
+}
+
+/// Search of the group start of a [StartGroupByParser] heuristic by the [reference]
+/// parse, written independently of the heuristics of the stream parsers
+pub fn start_group_by_parser<'a>(
+    heuristic: &StartGroupByParser<'a>,
+) -> impl Fn(&[u8]) -> Option<usize> + 'a {
+    let StartGroupByParser {
+        parser,
+        start_character,
+    } = heuristic.clone();
+    move |input| {
+        let start = (0..=input.len()).find(|&i| input[i..].starts_with(start_character))?;
+        let (_, skipped) = parser(&input[start..]).ok()?;
+        Some(start + skipped.len())
+    }
+}
+
+/// Parse the whole input with a plain nom loop, the expected items of the stream parsers
+///
+/// Data are parsed from each group start, given by `group_start` as a position in
+/// its input, or none when the rest of the input is discarded. On failure an error
+/// is yielded and the next group start is searched one byte further. The parsing
+/// ends once the rest of the input is an incomplete frame or discarded.
+pub fn reference<O>(
+    parser: ParserFunction<O>,
+    group_start: impl Fn(&[u8]) -> Option<usize>,
+    input: &[u8],
+) -> Vec<Option<O>> {
+    let mut items = vec![];
+    let mut position = 0;
+    loop {
+        let Some(start) = group_start(&input[position..]) else {
+            return items;
+        };
+        position += start;
+
+        let rest = &input[position..];
+        match parser(rest) {
+            Ok((remain, item)) => {
+                position += rest.len() - remain.len();
+                items.push(Some(item));
+            }
+            Err(nom::Err::Incomplete(_)) => return items,
+            Err(_) if rest.is_empty() => return items,
+            Err(_) => {
+                position += 1;
+                items.push(None);
+            }
+        }
+    }
+}
+
+fn chunks<'i>(input: &'i [u8], splits: &[usize]) -> Vec<&'i [u8]> {
+    let starts = std::iter::once(0).chain(splits.iter().copied());
+    let ends = splits.iter().copied().chain(std::iter::once(input.len()));
+    starts
+        .zip(ends)
+        .map(|(start, end)| &input[start..end])
+        .filter(|chunk| !chunk.is_empty())
+        .collect()
+}
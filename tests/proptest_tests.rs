@@ -0,0 +1,74 @@
+use proptest::prelude::*;
+
+use nom_stream_parser::buffers::preallocated::BufferPreallocated;
+use nom_stream_parser::stream_parsers::{sync_iterator, sync_reader};
+use nom_stream_parser::StartGroupByParser;
+use utils::source::Chunked;
+use utils::parsers::{parse_data, start_group_parenthesis};
+use utils::strategies::Scenario;
+
+fn heuristic() -> StartGroupByParser<'static> {
+    StartGroupByParser {
+        parser: start_group_parenthesis,
+        start_character: b"(",
+    }
+}
+
+/// Every item consumes at least a byte of the data or a chunk, a stream
+/// yielding more items doesn't end
+fn max_items(scenario: &Scenario<Vec<u8>>, data: &[u8]) -> usize {
+    data.len() + scenario.chunks(data).len() + 1
+}
+
+fn parse_iterator(scenario: &Scenario<Vec<u8>>, data: &[u8]) -> Vec<Option<Vec<u8>>> {
+    let mut work_buffer = BufferPreallocated::new(scenario.buffer_size);
+    let chunks = scenario.chunks(data);
+    sync_iterator::StreamParser::new(
+        chunks.into_iter(),
+        &mut work_buffer,
+        parse_data,
+        heuristic(),
+    )
+    .map(Result::ok)
+    .take(max_items(scenario, data) + 1)
+    .collect()
+}
+
+fn parse_reader(scenario: &Scenario<Vec<u8>>, data: &[u8]) -> Vec<Option<Vec<u8>>> {
+    let mut work_buffer = BufferPreallocated::new(scenario.buffer_size);
+    let reader = Chunked::new(scenario.chunks(data));
+    sync_reader::StreamParser::new(reader, &mut work_buffer, parse_data, heuristic())
+        .map(Result::ok)
+        .take(max_items(scenario, data) + 1)
+        .collect()
+}
+
+/// Whether the found values are all expected, each one at most once and in order
+fn is_ordered_subset(found: &[Vec<u8>], expected: &[Vec<u8>]) -> bool {
+    let mut expected = expected.iter();
+    found.iter().all(|value| expected.any(|x| x == value))
+}
+
+proptest! {
+    /// Frames may be lost when the work buffer overflows, the parsing
+    /// must go on until the end of the data
+    #[test]
+    fn no_frame_lost_duplicated_or_reordered(scenario: Scenario<Vec<u8>>) {
+        let data = scenario.data();
+        let expected = scenario.expected();
+
+        for items in [parse_iterator(&scenario, &data), parse_reader(&scenario, &data)] {
+            // The stream ends
+            prop_assert!(items.len() <= max_items(&scenario, &data), "{} items", items.len());
+
+            let found = items.into_iter().flatten().collect::<Vec<_>>();
+            if scenario.buffer_is_big_enough() {
+                // No frame lost, emitted twice nor out of order
+                prop_assert_eq!(&expected, &found);
+            } else {
+                // No frame emitted twice nor out of order
+                prop_assert!(is_ordered_subset(&found, &expected), "found {:?}", found);
+            }
+        }
+    }
+}
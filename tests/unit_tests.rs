@@ -1,8 +1,26 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use nom::bytes::complete::tag;
+use nom::combinator::map;
+use nom::sequence::delimited;
+use nom::{character, IResult};
+use rand::Rng;
+use rand_chacha::rand_core::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
 use nom_stream_parser::buffers::preallocated::BufferPreallocated;
-use nom_stream_parser::{heuristic::Increment, StartGroupByParser};
+use nom_stream_parser::timeout::ManualClock;
+use nom_stream_parser::{heuristic::Increment, Limits, StartGroupByParser, StreamParserError};
+use utils::generator::{Dataset, Generator, Parenthesis, Seeder};
 use utils::parsers::{parse_data, start_group_parenthesis};
+use utils::seeder::SeederConfig;
 use utils::source::Source;
 
+use equivalence::{start_group_by_parser, Driver, Equivalence};
+
+mod equivalence;
+
 #[test_pretty_log::test]
 fn test_stream_parser() {
     let data = b"(1,2,3,(4,5,6),7,8,9)(61,36,16,20,7)(62))(45,18,47,77,a,40,59,21)(21,6)<.(39,4,3)(76,47,83,55,33,5,10,20,28)R(2,63,67,40,57))(14,34)(";
@@ -61,3 +79,411 @@ fn test_stream_parser_increment() {
     let result = stream.flatten().collect::<Vec<Vec<u8>>>();
     assert_eq!(expected, result);
 }
+
+#[test_pretty_log::test]
+fn test_stream_parser_switch_parser() {
+    #[derive(Debug, PartialEq)]
+    enum Message {
+        Version(u8),
+        Record(Vec<u8>),
+    }
+
+    fn parse_version(input: &[u8]) -> IResult<&[u8], Message> {
+        map(
+            delimited(tag("V"), character::complete::u8, tag(";")),
+            Message::Version,
+        )(input)
+    }
+
+    fn parse_record(input: &[u8]) -> IResult<&[u8], Message> {
+        map(parse_data, Message::Record)(input)
+    }
+
+    let data = b"V2;(1,2)(3,4,5)#(6)";
+    let expected = vec![
+        Message::Version(2),
+        Message::Record(vec![1, 2]),
+        Message::Record(vec![3, 4, 5]),
+        Message::Record(vec![6]),
+    ];
+    let source = Source::new(data).with_chunk_size(8);
+    let mut work_buffer = BufferPreallocated::new(40).with_name("work buffer");
+
+    let mut stream = nom_stream_parser::stream_parsers::sync_iterator::StreamParser::new(
+        source,
+        &mut work_buffer,
+        parse_version,
+        Increment,
+    );
+
+    let mut result = vec![];
+    result.extend(stream.next().and_then(|x| x.ok()));
+    stream.switch_parser(parse_record);
+    result.extend(stream.flatten());
+
+    assert_eq!(expected, result);
+}
+
+#[test_pretty_log::test]
+fn test_stream_parser_switch_heuristic() {
+    let data = b"(1,2)(3)##(4)ab(5)";
+    let source = Source::new(data).with_chunk_size(8);
+    let mut work_buffer = BufferPreallocated::new(40).with_name("work buffer");
+
+    let mut stream = nom_stream_parser::stream_parsers::sync_iterator::StreamParser::new(
+        source,
+        &mut work_buffer,
+        parse_data,
+        Increment,
+    );
+    let mut result: Vec<_> = stream.by_ref().take(2).map(Result::ok).collect();
+
+    // The garbage is skipped by the new heuristic instead of yielding errors
+    let heuristic = StartGroupByParser {
+        parser: start_group_parenthesis,
+        start_character: b"(",
+    };
+    let stream = stream.switch_heuristic(heuristic);
+    result.extend(stream.map(Result::ok));
+
+    assert_eq!(
+        vec![
+            Some(vec![1, 2]),
+            Some(vec![3]),
+            Some(vec![4]),
+            Some(vec![5])
+        ],
+        result
+    );
+}
+
+#[test_pretty_log::test]
+fn test_stream_parser_stats() {
+    let data = b"##(1,2)ab(3)(x)";
+    let source = Source::new(data);
+    let mut work_buffer = BufferPreallocated::new(20);
+    let heuristic = StartGroupByParser {
+        parser: start_group_parenthesis,
+        start_character: b"(",
+    };
+    let mut stream = nom_stream_parser::stream_parsers::sync_iterator::StreamParser::new(
+        source,
+        &mut work_buffer,
+        parse_data,
+        heuristic,
+    );
+
+    let result = stream.by_ref().flatten().collect::<Vec<Vec<u8>>>();
+    assert_eq!(vec![vec![1, 2], vec![3]], result);
+
+    let stats = stream.stats();
+    assert_eq!(data.len() as u64, stats.bytes_read);
+    assert_eq!(2, stats.frames);
+    assert_eq!(1, stats.errors);
+    assert_eq!(6, stats.discarded);
+}
+
+#[test_pretty_log::test]
+fn test_chunking_equivalence() {
+    let heuristic = StartGroupByParser {
+        parser: start_group_parenthesis,
+        start_character: b"(",
+    };
+    let group_start = start_group_by_parser(&heuristic);
+    let equivalence = Equivalence::new(parse_data, heuristic, group_start, || {
+        BufferPreallocated::new(4096)
+    })
+    .with_max_chunk_size(8)
+    .with_random_chunkings(16);
+
+    equivalence.check(b"##(1,2)ab(3)(x)(4,(5,6)");
+    for seed in 0..8 {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let (data, _) = SeederConfig::new(10, 30, 2, 4, 4, 10, false).generate(&mut rng);
+        equivalence.check(&data);
+    }
+}
+
+#[test_pretty_log::test]
+fn test_chunking_equivalence_mismatch() {
+    // Complete parsers can't tell a truncated frame from an invalid one
+    fn parse_complete(input: &[u8]) -> IResult<&[u8], u8> {
+        delimited(tag("("), character::complete::u8, tag(")"))(input)
+    }
+
+    let heuristic = StartGroupByParser {
+        parser: start_group_parenthesis,
+        start_character: b"(",
+    };
+    let group_start = start_group_by_parser(&heuristic);
+    let mismatch = Equivalence::new(parse_complete, heuristic, group_start, || {
+        BufferPreallocated::new(64)
+    })
+    .verify(b"(1)(23)(4)")
+    .unwrap_err();
+
+    assert_eq!(Driver::Iterator, mismatch.driver);
+    assert_eq!(2, mismatch.chunks.len());
+    assert_eq!(vec![Some(1), Some(23), Some(4)], mismatch.expected);
+}
+
+#[test_pretty_log::test]
+fn test_stream_parser_adversarial_chunks() {
+    let data = b"##(1,2)ab(3)(x)(4,(5,6)(78,9)";
+    let parse = |source: Source| {
+        let mut work_buffer = BufferPreallocated::new(40);
+        let heuristic = StartGroupByParser {
+            parser: start_group_parenthesis,
+            start_character: b"(",
+        };
+        nom_stream_parser::stream_parsers::sync_iterator::StreamParser::new(
+            source,
+            &mut work_buffer,
+            parse_data,
+            heuristic,
+        )
+        .map(Result::ok)
+        .collect::<Vec<_>>()
+    };
+
+    let expected = parse(Source::new(data).with_chunk_size(data.len()));
+    assert_eq!(expected, parse(Source::new(data).with_chunk_size(1)));
+    assert_eq!(expected, parse(Source::new(data).with_empty_chunks()));
+    assert_eq!(
+        expected,
+        parse(Source::new(data).with_boundaries_at(b"(),"))
+    );
+    for seed in 0..20 {
+        let source = Source::new(data)
+            .with_random_chunk_size(seed, 6)
+            .with_empty_chunks()
+            .with_boundaries_at(b")");
+        assert_eq!(expected, parse(source), "seed {seed}");
+    }
+}
+
+/// Frames like `<42>`, to check generators other than the parenthesis one
+struct Angle;
+
+impl Generator for Angle {
+    type Value = u32;
+
+    fn frame(&self, rng: &mut ChaCha8Rng) -> (Vec<u8>, u32) {
+        let value = rng.gen_range(0..100_000);
+        (format!("<{value}>").into_bytes(), value)
+    }
+
+    fn noise(&self, rng: &mut ChaCha8Rng) -> Vec<u8> {
+        (0..rng.gen_range(0..8))
+            .map(|_| rng.gen_range(b'a'..=b'z'))
+            .collect()
+    }
+
+    fn corrupted(&self, rng: &mut ChaCha8Rng) -> Vec<u8> {
+        format!("<{}>", rng.gen_range(b'a'..=b'z') as char).into_bytes()
+    }
+}
+
+fn parse_angle(input: &[u8]) -> IResult<&[u8], u32> {
+    delimited(
+        nom::bytes::streaming::tag("<"),
+        character::streaming::u32,
+        nom::bytes::streaming::tag(">"),
+    )(input)
+}
+
+fn start_angle(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    nom::bytes::streaming::take_until("<")(input)
+}
+
+#[test_pretty_log::test]
+fn test_generators() {
+    for seed in 0..8 {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let seeder = Seeder::new(Parenthesis::default(), 50).with_corrupted_probability(10);
+        let (data, expected) = seeder.generate(&mut rng);
+        let mut work_buffer = BufferPreallocated::new(4096);
+        let heuristic = StartGroupByParser {
+            parser: start_group_parenthesis,
+            start_character: b"(",
+        };
+        let result: Vec<_> = nom_stream_parser::stream_parsers::sync_iterator::StreamParser::new(
+            Source::new(&data).with_random_chunk_size(seed, 8),
+            &mut work_buffer,
+            parse_data,
+            heuristic,
+        )
+        .flatten()
+        .collect();
+        assert_eq!(expected, result, "seed {seed}");
+
+        let seeder = Seeder::new(Angle, 50).with_corrupted_probability(10);
+        let (data, expected) = seeder.generate(&mut rng);
+        let mut work_buffer = BufferPreallocated::new(4096);
+        let heuristic = StartGroupByParser {
+            parser: start_angle,
+            start_character: b"<",
+        };
+        let result: Vec<_> = nom_stream_parser::stream_parsers::sync_reader::StreamParser::new(
+            data.as_slice(),
+            &mut work_buffer,
+            parse_angle,
+            heuristic,
+        )
+        .flatten()
+        .collect();
+        assert_eq!(expected, result, "seed {seed}");
+    }
+}
+
+#[test_pretty_log::test]
+fn test_stream_parser_exceeding_buffer() {
+    let chunks: Vec<&[u8]> = vec![b"(1,", b"2)(33,44,", b"(5)"];
+    let mut work_buffer = BufferPreallocated::new(6);
+    let heuristic = StartGroupByParser {
+        parser: start_group_parenthesis,
+        start_character: b"(",
+    };
+    let mut stream = nom_stream_parser::stream_parsers::sync_iterator::StreamParser::new(
+        chunks.into_iter(),
+        &mut work_buffer,
+        parse_data,
+        heuristic,
+    );
+    // The pending group is dropped along with the chunk which can't be buffered
+    assert!(stream.next().unwrap().is_err());
+    assert_eq!(12, stream.checkpoint().offset);
+    assert_eq!(1, stream.stats().errors);
+    assert_eq!(vec![vec![5]], stream.flatten().collect::<Vec<_>>());
+}
+
+#[test_pretty_log::test]
+fn test_stream_parser_limits() {
+    let parse = |data: &'static [u8], limits: Limits| {
+        let mut work_buffer = BufferPreallocated::new(20);
+        let heuristic = StartGroupByParser {
+            parser: start_group_parenthesis,
+            start_character: b"(",
+        };
+        nom_stream_parser::stream_parsers::sync_iterator::StreamParser::new(
+            Source::new(data).with_chunk_size(4),
+            &mut work_buffer,
+            parse_data,
+            heuristic,
+        )
+        .with_limits(limits)
+        .collect::<Vec<_>>()
+    };
+
+    let result = parse(
+        b"(1,2,3,4,5,6,7,8,9)(5)",
+        Limits::default().with_max_frame_size(8),
+    );
+    assert!(matches!(
+        &result[..],
+        [
+            Err(StreamParserError::FrameTooLarge {
+                max_frame_size: 8,
+                dropped: 12
+            }),
+            Ok(data),
+        ] if data == &vec![5]
+    ));
+
+    let result = parse(
+        b"(1)abcdefghijklmnop(2)",
+        Limits::default().with_max_garbage(8),
+    );
+    assert!(matches!(
+        &result[..],
+        [
+            Ok(first),
+            Err(StreamParserError::GarbageExceeded { max_garbage: 8 }),
+            Ok(second),
+        ] if first == &vec![1] && second == &vec![2]
+    ));
+}
+
+#[test_pretty_log::test]
+fn test_stream_parser_timeouts() {
+    // Each chunk is received once the clock moved forward by the given seconds
+    let parse = |steps: Vec<(u64, &'static [u8])>, frame: u64, idle: u64| {
+        let clock = Arc::new(ManualClock::default());
+        let chunks_clock = clock.clone();
+        let chunks = steps.into_iter().map(move |(elapsed, chunk)| {
+            chunks_clock.advance(Duration::from_secs(elapsed));
+            chunk
+        });
+        let mut work_buffer = BufferPreallocated::new(20);
+        let heuristic = StartGroupByParser {
+            parser: start_group_parenthesis,
+            start_character: b"(",
+        };
+        nom_stream_parser::stream_parsers::sync_iterator::StreamParser::new(
+            chunks,
+            &mut work_buffer,
+            parse_data,
+            heuristic,
+        )
+        .with_clock(clock)
+        .with_frame_timeout(Duration::from_secs(frame))
+        .with_idle_timeout(Duration::from_secs(idle))
+        .collect::<Vec<_>>()
+    };
+
+    let result = parse(vec![(0, b"(1,"), (5, b"2)(3"), (1, b",4)")], 3, 10);
+    assert!(matches!(
+        &result[..],
+        [Err(StreamParserError::Timeout { dropped }), Ok(data)]
+            if dropped == b"(1," && data == &vec![3, 4]
+    ));
+
+    // An empty chunk means that no data are available yet
+    let result = parse(vec![(0, b"(1)"), (2, b""), (2, b""), (1, b"(2)")], 3, 3);
+    assert!(matches!(
+        &result[..],
+        [Ok(first), Err(StreamParserError::Idle { idle }), Ok(second)]
+            if first == &vec![1] && *idle == Duration::from_secs(3) && second == &vec![2]
+    ));
+}
+
+#[test_pretty_log::test]
+fn test_stream_parser_reader_full_buffer() {
+    let parse = |data: &'static [u8], buffer_size| {
+        let mut work_buffer = BufferPreallocated::new(buffer_size);
+        let heuristic = StartGroupByParser {
+            parser: start_group_parenthesis,
+            start_character: b"(",
+        };
+        nom_stream_parser::stream_parsers::sync_reader::StreamParser::new(
+            data,
+            &mut work_buffer,
+            parse_data,
+            heuristic,
+        )
+        .collect::<Vec<_>>()
+    };
+
+    // The incomplete frame filling the work buffer is the end of the data
+    let result = parse(b"(1)(2,3", 4);
+    assert!(matches!(&result[..], [Ok(data)] if data == &vec![1]));
+
+    let result = parse(b"(1,2,3)(4)", 4);
+    assert!(matches!(
+        &result[..],
+        [
+            Err(StreamParserError::ExceededBufferUnknownSize { buffer_size: 4 }),
+            Ok(data),
+        ] if data == &vec![4]
+    ));
+
+    // The byte read to find out more data follow is kept
+    let result = parse(b"(1,2,3(4)", 6);
+    assert!(matches!(
+        &result[..],
+        [
+            Err(StreamParserError::ExceededBufferUnknownSize { buffer_size: 6 }),
+            Ok(data),
+        ] if data == &vec![4]
+    ));
+}
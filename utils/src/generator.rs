@@ -0,0 +1,161 @@
+use std::fmt::{Display, Formatter};
+
+use rand::Rng;
+use rand_chacha::ChaCha8Rng;
+
+use crate::seeder::{
+    bool_with_prob, generate_garbage_between_groups, generate_group_data, SeederConfig,
+};
+
+/// Generate the frames of a format, the value expected from parsing them,
+/// the noise found between frames and frames the parser must reject
+pub trait Generator {
+    type Value;
+
+    /// Generate a valid frame with the value the parser must yield
+    fn frame(&self, rng: &mut ChaCha8Rng) -> (Vec<u8>, Self::Value);
+    /// Generate noise, skipped by the heuristic without hiding the next frame
+    fn noise(&self, rng: &mut ChaCha8Rng) -> Vec<u8>;
+    /// Generate a frame starting like a valid one but rejected by the parser
+    fn corrupted(&self, rng: &mut ChaCha8Rng) -> Vec<u8>;
+}
+
+/// Data set made of generated frames and the values expected from parsing them
+pub trait Dataset: Display {
+    type Value;
+
+    fn generate(&self, rng: &mut ChaCha8Rng) -> (Vec<u8>, Vec<Self::Value>);
+}
+
+/// Generate data sets from any generator
+pub struct Seeder<G> {
+    generator: G,
+    number_of_frames: usize,
+    /// Probability in percent to generate noise before a frame
+    noise_probability: u64,
+    /// Probability in percent to generate a corrupted frame instead of a valid one
+    corrupted_probability: u64,
+}
+
+impl<G: Generator> Seeder<G> {
+    pub fn new(generator: G, number_of_frames: usize) -> Self {
+        Seeder {
+            generator,
+            number_of_frames,
+            noise_probability: 30,
+            corrupted_probability: 2,
+        }
+    }
+
+    pub fn with_noise_probability(mut self, noise_probability: u64) -> Self {
+        self.noise_probability = noise_probability;
+        self
+    }
+
+    pub fn with_corrupted_probability(mut self, corrupted_probability: u64) -> Self {
+        self.corrupted_probability = corrupted_probability;
+        self
+    }
+}
+
+impl<G> Display for Seeder<G> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "frames = {}, noise_prob = {}%, corrupted_prob = {}% ",
+            self.number_of_frames, self.noise_probability, self.corrupted_probability
+        )
+    }
+}
+
+impl<G: Generator> Dataset for Seeder<G> {
+    type Value = G::Value;
+
+    fn generate(&self, rng: &mut ChaCha8Rng) -> (Vec<u8>, Vec<G::Value>) {
+        let mut data = vec![];
+        let mut expected = vec![];
+
+        for _ in 0..self.number_of_frames {
+            if bool_with_prob(self.noise_probability, rng) {
+                data.extend(self.generator.noise(rng));
+            }
+            if bool_with_prob(self.corrupted_probability, rng) {
+                data.extend(self.generator.corrupted(rng));
+            } else {
+                let (frame, value) = self.generator.frame(rng);
+                data.extend(frame);
+                expected.push(value);
+            }
+        }
+        if bool_with_prob(self.noise_probability, rng) {
+            data.extend(self.generator.noise(rng));
+        }
+        (data, expected)
+    }
+}
+
+impl Dataset for SeederConfig {
+    type Value = Vec<u8>;
+
+    fn generate(&self, rng: &mut ChaCha8Rng) -> (Vec<u8>, Vec<Vec<u8>>) {
+        SeederConfig::generate(self, rng)
+    }
+}
+
+/// The groups and the garbage of the configuration, with the sizes of the configuration
+impl Generator for SeederConfig {
+    type Value = Vec<u8>;
+
+    fn frame(&self, rng: &mut ChaCha8Rng) -> (Vec<u8>, Vec<u8>) {
+        self.parenthesis().frame(rng)
+    }
+
+    fn noise(&self, rng: &mut ChaCha8Rng) -> Vec<u8> {
+        self.parenthesis().noise(rng)
+    }
+
+    fn corrupted(&self, rng: &mut ChaCha8Rng) -> Vec<u8> {
+        self.parenthesis().corrupted(rng)
+    }
+}
+
+/// Groups of numbers like `(1,2,3)`, parsed by [parse_data](crate::parsers::parse_data),
+/// separated by ASCII garbage, generated like the groups of [SeederConfig]
+#[derive(Debug, Clone)]
+pub struct Parenthesis {
+    /// Maximum number of elements of a group
+    pub max_elements: usize,
+    /// Maximum length of the garbage between two groups
+    pub max_garbage: u64,
+}
+
+impl Default for Parenthesis {
+    fn default() -> Self {
+        Parenthesis {
+            max_elements: 10,
+            max_garbage: 4,
+        }
+    }
+}
+
+impl Generator for Parenthesis {
+    type Value = Vec<u8>;
+
+    fn frame(&self, rng: &mut ChaCha8Rng) -> (Vec<u8>, Vec<u8>) {
+        let elements = rng.gen_range(1..=self.max_elements.max(1));
+        let (group, _, numbers) = generate_group_data(rng, elements, 0, 0, false);
+        (group, numbers)
+    }
+
+    fn noise(&self, rng: &mut ChaCha8Rng) -> Vec<u8> {
+        let amount_of_garbage = rng.gen_range(0..self.max_garbage.max(1)) as usize;
+        generate_garbage_between_groups(amount_of_garbage, rng, false)
+    }
+
+    /// Every element of the group is replaced by a letter
+    fn corrupted(&self, rng: &mut ChaCha8Rng) -> Vec<u8> {
+        let elements = rng.gen_range(1..=self.max_elements.max(1));
+        let (group, _, _) = generate_group_data(rng, elements, 100, 100, false);
+        group
+    }
+}
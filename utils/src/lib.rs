@@ -5,6 +5,9 @@ macro_rules! debug {
     };
 }
 
+pub mod generator;
 pub mod parsers;
 pub mod seeder;
 pub mod source;
+#[cfg(feature = "proptest")]
+pub mod strategies;
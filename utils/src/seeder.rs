@@ -3,6 +3,7 @@ use std::fmt::{Display, Formatter};
 use rand::{Rng, RngCore, SeedableRng};
 use rand_chacha::ChaCha8Rng;
 
+use crate::generator::{Dataset, Parenthesis};
 use crate::source::Source;
 
 pub struct SeederConfig {
@@ -48,6 +49,14 @@ impl SeederConfig {
         }
     }
 
+    /// Generator of groups and garbage of the sizes of the configuration
+    pub fn parenthesis(&self) -> Parenthesis {
+        Parenthesis {
+            max_elements: self.max_element_per_group_number,
+            max_garbage: self.max_garbage_element_between_groups,
+        }
+    }
+
     pub fn generate(&self, rng: &mut ChaCha8Rng) -> (Vec<u8>, Vec<Vec<u8>>) {
         generate_groups_data(
             rng,
@@ -62,7 +71,7 @@ impl SeederConfig {
     }
 }
 
-fn generate_garbage_between_groups(
+pub(crate) fn generate_garbage_between_groups(
     amount_of_garbage: usize,
     rng: &mut ChaCha8Rng,
     _debug: bool,
@@ -139,7 +148,7 @@ pub fn generate_groups_data(
     (result_data, expected)
 }
 
-fn generate_group_data(
+pub(crate) fn generate_group_data(
     rng: &mut ChaCha8Rng,
     element_per_group_number: usize,
     probability_to_generate_failed_group: u64,
@@ -218,13 +227,14 @@ fn generate_group_data(
 }
 
 /// Generate a true value with the defined probability
-fn bool_with_prob(prob: u64, rng: &mut ChaCha8Rng) -> bool {
+pub(crate) fn bool_with_prob(prob: u64, rng: &mut ChaCha8Rng) -> bool {
     let random = (rng.next_u64() % 100) + 1;
     random <= prob
 }
 
-pub fn source_data<F>(config: &SeederConfig, seed: u64, chunk_size: usize, mut closure: F)
+pub fn source_data<D, F>(config: &D, seed: u64, chunk_size: usize, mut closure: F)
 where
+    D: Dataset,
     F: FnMut(Source),
 {
     let mut rng = ChaCha8Rng::seed_from_u64(seed);
@@ -232,8 +242,9 @@ where
     closure(Source::new(&data).with_chunk_size(chunk_size))
 }
 
-pub fn raw_data<F>(config: &SeederConfig, seed: u64, mut closure: F)
+pub fn raw_data<D, F>(config: &D, seed: u64, mut closure: F)
 where
+    D: Dataset,
     F: FnMut(&[u8]),
 {
     let mut rng = ChaCha8Rng::seed_from_u64(seed);
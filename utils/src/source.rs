@@ -1,8 +1,24 @@
+use std::io;
+use std::io::{ErrorKind, Read};
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
 #[derive(Clone)]
 pub struct Source<'a> {
     data: &'a [u8],
     cursor: usize,
     chunk_size: usize,
+    /// Random chunk sizes up to a maximum, instead of the fixed chunk size
+    random: Option<(ChaCha8Rng, usize)>,
+    /// Yield an empty chunk between two chunks
+    empty_chunks: bool,
+    /// Whether the next chunk is an empty one
+    empty_pending: bool,
+    /// Bytes isolated in their own chunk
+    markers: &'a [u8],
+    /// Rest of the chunk partially read
+    pending: &'a [u8],
 }
 
 impl Source<'_> {
@@ -16,20 +32,33 @@ impl<'a> Iterator for Source<'a> {
     type Item = &'a [u8];
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.cursor + self.chunk_size > self.data.len() {
-            let final_data = &self.data[self.cursor..];
-            self.cursor += final_data.len();
+        if self.cursor >= self.data.len() {
+            return None;
+        }
+        if self.empty_pending {
+            self.empty_pending = false;
+            return Some(&[]);
+        }
+
+        let size = match &mut self.random {
+            Some((rng, max_chunk_size)) => rng.gen_range(1..=*max_chunk_size),
+            None => self.chunk_size,
+        };
+        let remaining = &self.data[self.cursor..];
+        let mut end = size.min(remaining.len());
 
-            return if final_data.is_empty() {
-                None
-            } else {
-                Some(final_data)
-            };
+        // Chunk boundaries are placed right before and right after each marker
+        if let Some(position) = remaining[..end]
+            .iter()
+            .position(|byte| self.markers.contains(byte))
+        {
+            end = position.max(1);
         }
 
-        let next_data = Some(&self.data[self.cursor..self.cursor + self.chunk_size]);
-        self.cursor += self.chunk_size;
-        next_data
+        let next_data = &remaining[..end];
+        self.cursor += end;
+        self.empty_pending = self.empty_chunks;
+        Some(next_data)
     }
 }
 
@@ -39,13 +68,141 @@ impl<'a> Source<'a> {
             data,
             cursor: 0,
             chunk_size: 4,
+            random: None,
+            empty_chunks: false,
+            empty_pending: false,
+            markers: &[],
+            pending: &[],
         }
     }
     pub fn with_chunk_size(self, chunk_size: usize) -> Self {
         Self {
-            data: self.data,
             chunk_size,
-            cursor: self.cursor,
+            random: None,
+            ..self
+        }
+    }
+    /// Yield chunks of seeded random sizes between 1 and `max_chunk_size`
+    pub fn with_random_chunk_size(self, seed: u64, max_chunk_size: usize) -> Self {
+        Self {
+            random: Some((ChaCha8Rng::seed_from_u64(seed), max_chunk_size.max(1))),
+            ..self
+        }
+    }
+    /// Yield an empty chunk between two chunks
+    pub fn with_empty_chunks(self) -> Self {
+        Self {
+            empty_chunks: true,
+            ..self
+        }
+    }
+    /// Isolate each of the marker bytes in its own chunk, for instance
+    /// the delimiters of a group, so boundaries fall right around them
+    pub fn with_boundaries_at(self, markers: &'a [u8]) -> Self {
+        Self { markers, ..self }
+    }
+}
+
+/// Read the data chunk by chunk, a read never returns more than a chunk
+impl Read for Source<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pending.is_empty() {
+            match self.next() {
+                Some(chunk) => self.pending = chunk,
+                None => return Ok(0),
+            }
+        }
+        let size = buf.len().min(self.pending.len());
+        buf[..size].copy_from_slice(&self.pending[..size]);
+        self.pending = &self.pending[size..];
+        Ok(size)
+    }
+}
+
+/// Reader wrapper behaving like an OS reader under pressure, it
+/// returns short reads and randomly fails with [ErrorKind::Interrupted]
+/// or [ErrorKind::WouldBlock] before returning the data
+pub struct FlakyReader<R> {
+    inner: R,
+    rng: ChaCha8Rng,
+    max_read: Option<usize>,
+    /// Percentage of reads interrupted
+    interrupted: u32,
+    /// Percentage of reads returning no data yet
+    would_block: u32,
+}
+
+impl<R: Read> FlakyReader<R> {
+    pub fn new(inner: R, seed: u64) -> Self {
+        FlakyReader {
+            inner,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            max_read: None,
+            interrupted: 0,
+            would_block: 0,
+        }
+    }
+    /// Read randomly between 1 and `max_read` bytes at once
+    pub fn with_short_reads(self, max_read: usize) -> Self {
+        Self {
+            max_read: Some(max_read.max(1)),
+            ..self
+        }
+    }
+    /// Interrupt this percentage of the reads
+    pub fn with_interrupted(self, percent: u32) -> Self {
+        Self {
+            interrupted: percent.min(100),
+            ..self
+        }
+    }
+    /// Return no data yet for this percentage of the reads
+    pub fn with_would_block(self, percent: u32) -> Self {
+        Self {
+            would_block: percent.min(100),
+            ..self
+        }
+    }
+}
+
+impl<R: Read> Read for FlakyReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.rng.gen_range(0..100) < self.interrupted {
+            return Err(ErrorKind::Interrupted.into());
+        }
+        if self.rng.gen_range(0..100) < self.would_block {
+            return Err(ErrorKind::WouldBlock.into());
+        }
+        let size = match self.max_read {
+            Some(max_read) => buf.len().min(self.rng.gen_range(1..=max_read)),
+            None => buf.len(),
+        };
+        self.inner.read(&mut buf[..size])
+    }
+}
+
+/// Reader returning at most one chunk per read
+pub struct Chunked<'i> {
+    chunks: Vec<&'i [u8]>,
+}
+
+impl<'i> Chunked<'i> {
+    pub fn new(chunks: Vec<&'i [u8]>) -> Self {
+        Chunked { chunks }
+    }
+}
+
+impl Read for Chunked<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let Some(chunk) = self.chunks.first_mut() else {
+            return Ok(0);
+        };
+        let size = buf.len().min(chunk.len());
+        buf[..size].copy_from_slice(&chunk[..size]);
+        *chunk = &chunk[size..];
+        if chunk.is_empty() {
+            self.chunks.remove(0);
         }
+        Ok(size)
     }
 }
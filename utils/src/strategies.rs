@@ -0,0 +1,146 @@
+use proptest::arbitrary::Arbitrary;
+use proptest::collection::vec;
+use proptest::prelude::*;
+use proptest::strategy::BoxedStrategy;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use crate::generator::{Generator, Parenthesis};
+
+/// A piece of a generated stream
+#[derive(Clone)]
+pub enum Part<V> {
+    /// A valid frame and the value the parser must yield
+    Frame(Vec<u8>, V),
+    /// Data skipped by the heuristic
+    Noise(Vec<u8>),
+    /// A frame rejected by the parser
+    Corrupted(Vec<u8>),
+}
+
+impl<V> Part<V> {
+    pub fn bytes(&self) -> &[u8] {
+        match self {
+            Part::Frame(bytes, _) | Part::Noise(bytes) | Part::Corrupted(bytes) => bytes,
+        }
+    }
+}
+
+impl<V: std::fmt::Debug> std::fmt::Debug for Part<V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Part::Frame(bytes, value) => write!(f, "Frame({:?} => {value:?})", debug!(bytes)),
+            Part::Noise(bytes) => write!(f, "Noise({:?})", debug!(bytes)),
+            Part::Corrupted(bytes) => write!(f, "Corrupted({:?})", debug!(bytes)),
+        }
+    }
+}
+
+/// A stream to parse: its parts, the chunking of the data and the work buffer size
+#[derive(Clone, Debug)]
+pub struct Scenario<V> {
+    pub parts: Vec<Part<V>>,
+    /// Sizes of the successive chunks, repeated until all the data are chunked
+    pub chunk_sizes: Vec<usize>,
+    pub buffer_size: usize,
+}
+
+impl<V: Clone> Scenario<V> {
+    pub fn data(&self) -> Vec<u8> {
+        self.parts.iter().flat_map(Part::bytes).copied().collect()
+    }
+
+    /// Values of the valid frames, in the order of the stream
+    pub fn expected(&self) -> Vec<V> {
+        self.parts
+            .iter()
+            .filter_map(|part| match part {
+                Part::Frame(_, value) => Some(value.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Split the data following the chunk sizes
+    pub fn chunks<'d>(&self, data: &'d [u8]) -> Vec<&'d [u8]> {
+        let mut chunks = vec![];
+        let mut sizes = self.chunk_sizes.iter().cycle();
+        let mut remaining = data;
+        while let (false, Some(&size)) = (remaining.is_empty(), sizes.next()) {
+            let (chunk, rest) = remaining.split_at(size.clamp(1, remaining.len()));
+            chunks.push(chunk);
+            remaining = rest;
+        }
+        chunks
+    }
+
+    /// Whether the work buffer can always hold a partial frame and the next chunk
+    pub fn buffer_is_big_enough(&self) -> bool {
+        let part = self.parts.iter().map(|part| part.bytes().len()).max();
+        let chunk = self.chunk_sizes.iter().max();
+        self.buffer_size >= part.unwrap_or(0) + chunk.unwrap_or(&0)
+    }
+}
+
+/// Build scenarios from strategies of frames, noise and corrupted frames,
+/// with chunks up to `max_chunk_size` and a work buffer up to `max_buffer_size`
+pub fn scenario<V: Clone + std::fmt::Debug + 'static>(
+    frame: impl Strategy<Value = (Vec<u8>, V)> + 'static,
+    noise: impl Strategy<Value = Vec<u8>> + 'static,
+    corrupted: impl Strategy<Value = Vec<u8>> + 'static,
+    max_chunk_size: usize,
+    max_buffer_size: usize,
+) -> BoxedStrategy<Scenario<V>> {
+    let part = prop_oneof![
+        6 => frame.prop_map(|(bytes, value)| Part::Frame(bytes, value)),
+        3 => noise.prop_map(Part::Noise),
+        1 => corrupted.prop_map(Part::Corrupted),
+    ];
+    (
+        vec(part, 0..32),
+        vec(1..=max_chunk_size, 1..8),
+        1..=max_buffer_size,
+    )
+        .prop_map(|(parts, chunk_sizes, buffer_size)| Scenario {
+            parts,
+            chunk_sizes,
+            buffer_size,
+        })
+        .boxed()
+}
+
+/// Build scenarios from the frames, noise and corrupted frames of a generator,
+/// each part is generated from a seed drawn by the strategy
+pub fn generated_scenario<G>(
+    generator: G,
+    max_chunk_size: usize,
+    max_buffer_size: usize,
+) -> BoxedStrategy<Scenario<G::Value>>
+where
+    G: Generator + Clone + 'static,
+    G::Value: Clone + std::fmt::Debug + 'static,
+{
+    let seeded = || any::<u64>().prop_map(ChaCha8Rng::seed_from_u64);
+    let (frame, noise, corrupted) = (generator.clone(), generator.clone(), generator);
+    scenario(
+        seeded().prop_map(move |mut rng| frame.frame(&mut rng)),
+        seeded().prop_map(move |mut rng| noise.noise(&mut rng)),
+        seeded().prop_map(move |mut rng| corrupted.corrupted(&mut rng)),
+        max_chunk_size,
+        max_buffer_size,
+    )
+}
+
+impl Arbitrary for Scenario<Vec<u8>> {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    /// Scenarios of the parenthesis format
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        let generator = Parenthesis {
+            max_elements: 7,
+            max_garbage: 8,
+        };
+        generated_scenario(generator, 16, 64)
+    }
+}